@@ -0,0 +1,187 @@
+// ---------- Accelerators ----------
+// Hotkeys are written as "Ctrl+Shift+F6" / "Super+c": any number of
+// modifier names followed by one X11 keysym name. A bare keysym ("F6")
+// still works and means "no modifiers". "Button8" in place of the keysym
+// names a mouse button instead of a key.
+
+use std::fmt;
+
+use anyhow::{bail, Result};
+use x11::xlib::*;
+
+/// Modifier names accepted in accelerators, first spelling is canonical.
+const MODIFIERS: [(&[&str], u32); 5] = [
+    (&["Ctrl", "Control"], ControlMask),
+    (&["Shift"], ShiftMask),
+    (&["Alt", "Mod1", "Meta"], Mod1Mask),
+    (&["Super", "Win", "Mod4"], Mod4Mask),
+    (&["Hyper", "Mod3"], Mod3Mask),
+];
+
+/// Modifier bits that take part in matching; Lock/NumLock/Mod5 are
+/// covered by MOD_VARIANTS instead.
+pub const RELEVANT_MODS: u32 = ControlMask | ShiftMask | Mod1Mask | Mod3Mask | Mod4Mask;
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Accel {
+    pub mods: u32,
+    pub key: String,
+}
+
+impl Accel {
+    pub fn parse(text: &str) -> Result<Self> {
+        let parts: Vec<&str> = text.split('+').map(str::trim).collect();
+        let Some((key, mods)) = parts.split_last() else {
+            bail!("empty hotkey");
+        };
+        if key.is_empty() {
+            bail!("hotkey '{text}' has no key after the modifiers");
+        }
+        let mut mask = 0;
+        for m in mods {
+            let Some((_, bit)) = MODIFIERS
+                .iter()
+                .find(|(names, _)| names.iter().any(|n| n.eq_ignore_ascii_case(m)))
+            else {
+                bail!("unknown modifier '{m}' in '{text}' (use Ctrl, Shift, Alt, Super, Hyper)");
+            };
+            mask |= bit;
+        }
+        Ok(Self { mods: mask, key: key.to_string() })
+    }
+
+    /// Mouse buttons the capture widget binds: the extra (side) buttons.
+    /// 1 would swallow every left click and 4..7 are the scroll wheel.
+    pub const BUTTONS: std::ops::RangeInclusive<u32> = 8..=32;
+
+    /// Middle and right click work too, but grabbing them takes every
+    /// such click away from other programs, so they have to be typed out.
+    pub const SHARED_BUTTONS: [u32; 2] = [2, 3];
+
+    /// Whether mouse button `b` may be a hotkey at all.
+    pub fn usable_button(b: u32) -> bool {
+        Self::BUTTONS.contains(&b) || Self::SHARED_BUTTONS.contains(&b)
+    }
+
+    /// The button number for "ButtonN" accelerators.
+    pub fn button(&self) -> Option<u32> {
+        let n = self.key.get(..6)?.eq_ignore_ascii_case("button").then(|| &self.key[6..])?;
+        n.parse().ok()
+    }
+
+    /// Parse and check that the key names a real keysym or usable button;
+    /// returns the canonical spelling. Needs no display connection.
+    pub fn validate(text: &str) -> Result<String> {
+        let mut accel = Self::parse(text)?;
+        if let Some(b) = accel.button() {
+            if !Self::usable_button(b) {
+                bail!("mouse button hotkeys must be Button2, Button3 or Button8..Button32");
+            }
+            accel.key = format!("Button{b}");
+            return Ok(accel.to_string());
+        }
+        let c = std::ffi::CString::new(accel.key.as_str())?;
+        if unsafe { XStringToKeysym(c.as_ptr()) } == 0 {
+            bail!("Unknown hotkey keysym '{}'", accel.key);
+        }
+        Ok(accel.to_string())
+    }
+
+    /// Keysym names to press in order to type this chord: the modifiers'
+    /// left-hand keys, then the key itself.
+    pub fn keysyms(&self) -> Vec<String> {
+        const KEYS: [(u32, &str); 5] = [
+            (ControlMask, "Control_L"),
+            (ShiftMask, "Shift_L"),
+            (Mod1Mask, "Alt_L"),
+            (Mod4Mask, "Super_L"),
+            (Mod3Mask, "Hyper_L"),
+        ];
+        let mods = KEYS.iter().filter(|(bit, _)| self.mods & bit != 0);
+        mods.map(|(_, k)| k.to_string()).chain([self.key.clone()]).collect()
+    }
+}
+
+impl fmt::Display for Accel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (names, bit) in MODIFIERS {
+            if self.mods & bit != 0 {
+                write!(f, "{}+", names[0])?;
+            }
+        }
+        write!(f, "{}", self.key)
+    }
+}
+
+/// What has to be pressed, without modifiers.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Trigger {
+    Key(u32), // keycode
+    Button(u32),
+}
+
+/// An accelerator resolved against the current keyboard mapping.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct KeyCombo {
+    pub trigger: Trigger,
+    pub mods: u32,
+}
+
+impl KeyCombo {
+    /// Does a press of `trigger` with this modifier state trigger us?
+    pub fn matches(&self, trigger: Trigger, state: u32) -> bool {
+        trigger == self.trigger && state & RELEVANT_MODS == self.mods
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn modifier_aliases_and_case() {
+        let a = Accel::parse("control+META+win+Mod3+shift+F6").unwrap();
+        assert_eq!(a.mods, ControlMask | Mod1Mask | Mod4Mask | Mod3Mask | ShiftMask);
+        assert_eq!(a.key, "F6");
+        // Spaces around '+' are fine; the key keeps its case
+        assert_eq!(Accel::parse(" Alt + q ").unwrap(), Accel { mods: Mod1Mask, key: "q".into() });
+        assert_eq!(Accel::parse("F6").unwrap().mods, 0);
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!(Accel::parse("").is_err());
+        let e = Accel::parse("Ctrl+").unwrap_err().to_string();
+        assert!(e.contains("no key after the modifiers"), "{e}");
+        let e = Accel::parse("Fn+F6").unwrap_err().to_string();
+        assert!(e.contains("unknown modifier 'Fn'"), "{e}");
+    }
+
+    #[test]
+    fn display_is_canonical() {
+        for (text, canonical) in [
+            ("shift+ctrl+F6", "Ctrl+Shift+F6"),
+            ("Mod4+Control+Mod1+c", "Ctrl+Alt+Super+c"),
+            ("Hyper+x", "Hyper+x"),
+            ("space", "space"),
+        ] {
+            let a = Accel::parse(text).unwrap();
+            assert_eq!(a.to_string(), canonical);
+            assert_eq!(Accel::parse(canonical).unwrap(), a);
+        }
+    }
+
+    #[test]
+    fn buttons() {
+        assert_eq!(Accel::parse("Button8").unwrap().button(), Some(8));
+        assert_eq!(Accel::parse("Ctrl+BUTTON9").unwrap().button(), Some(9));
+        assert_eq!(Accel::parse("Buttonx").unwrap().button(), None);
+        assert_eq!(Accel::parse("Butt").unwrap().button(), None);
+        assert_eq!(Accel::parse("F6").unwrap().button(), None);
+        // Canonical spelling; the wheel and left click are refused
+        assert_eq!(Accel::validate("shift+button3").unwrap(), "Shift+Button3");
+        for b in ["Button1", "Button4", "Button7", "Button33"] {
+            assert!(Accel::validate(b).is_err(), "{b}");
+        }
+    }
+}
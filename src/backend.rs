@@ -0,0 +1,780 @@
+// ---------- Click backends ----------
+// click_thread injects input through one of these. XTest goes through the
+// X server; uinput creates a kernel virtual mouse/keyboard that any display
+// server (including Wayland compositors) sees as real hardware; SendEvent
+// delivers synthetic events straight to one window without touching the
+// pointer; Recording only remembers what it was asked to do, for dry runs
+// and tests.
+
+use std::{
+    collections::HashMap,
+    ffi::CString,
+    fs::{File, OpenOptions},
+    io::Write,
+    os::{fd::AsRawFd, unix::fs::OpenOptionsExt},
+    ptr, thread,
+    time::Duration,
+};
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use x11::xlib::*;
+use x11::xtest::*;
+
+use crate::{
+    targets::{self, Point, TargetWindow},
+    xerror,
+};
+
+#[derive(Clone, Copy, PartialEq, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BackendKind {
+    #[default]
+    XTest,
+    UInput,
+    Window,
+    Record,
+}
+
+impl BackendKind {
+    pub const ALL: [BackendKind; 4] = [
+        BackendKind::XTest,
+        BackendKind::UInput,
+        BackendKind::Window,
+        BackendKind::Record,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            BackendKind::XTest => "xtest",
+            BackendKind::UInput => "uinput",
+            BackendKind::Window => "window",
+            BackendKind::Record => "record",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|k| k.name() == name)
+    }
+}
+
+/// One request made to a backend.
+#[derive(Clone, PartialEq, Debug)]
+pub enum Action {
+    Press(u32),
+    Release(u32),
+    Move(i32, i32),
+    Scroll { dx: i32, dy: i32 },
+    /// In 1/120 of a tick.
+    ScrollHiRes { dx: i32, dy: i32 },
+    Key { key: String, down: bool },
+}
+
+pub trait ClickBackend {
+    /// Press / release an X button number (1 left, 2 middle, 3 right, ...).
+    fn press(&mut self, button: u32) -> Result<()>;
+    fn release(&mut self, button: u32) -> Result<()>;
+    /// Move the pointer to root-window coordinates.
+    fn move_to(&mut self, p: Point) -> Result<()>;
+    /// Where the pointer is, if the backend can tell.
+    fn position(&mut self) -> Option<Point>;
+    /// Wheel steps; positive `dy` scrolls down, positive `dx` right.
+    fn scroll(&mut self, dx: i32, dy: i32) -> Result<()>;
+    /// Like scroll() in 1/120 of a tick, for smooth scrolling.
+    fn scroll_hi_res(&mut self, _dx: i32, _dy: i32) -> Result<()> {
+        bail!("smooth scrolling needs the uinput backend")
+    }
+    /// Press or release a key given by keysym name ("space", "e", "F5").
+    fn key(&mut self, key: &str, down: bool) -> Result<()>;
+    /// Push buffered requests out.
+    fn flush(&mut self) {}
+}
+
+/// `window` is only used by BackendKind::Window.
+pub fn open(kind: BackendKind, window: &TargetWindow) -> Result<Box<dyn ClickBackend>> {
+    Ok(match kind {
+        BackendKind::XTest => Box::new(XTest::new()?),
+        BackendKind::UInput => Box::new(UInput::new()?),
+        BackendKind::Window => Box::new(SendEvent::new(window)?),
+        BackendKind::Record => Box::new(Recording::default()),
+    })
+}
+
+// ---------- XTest ----------
+
+pub struct XTest {
+    dpy: *mut Display,
+    keycodes: HashMap<String, u32>,
+}
+
+impl XTest {
+    pub fn new() -> Result<Self> {
+        let dpy = unsafe { XOpenDisplay(ptr::null()) };
+        if dpy.is_null() {
+            bail!("XTest: failed to open X display (X11 only)");
+        }
+        Ok(Self { dpy, keycodes: HashMap::new() })
+    }
+
+    fn button(&mut self, button: u32, down: bool) {
+        unsafe { XTestFakeButtonEvent(self.dpy, button, down as i32, CurrentTime) };
+    }
+}
+
+impl ClickBackend for XTest {
+    fn press(&mut self, button: u32) -> Result<()> {
+        self.button(button, true);
+        Ok(())
+    }
+
+    fn release(&mut self, button: u32) -> Result<()> {
+        self.button(button, false);
+        Ok(())
+    }
+
+    fn move_to(&mut self, p: Point) -> Result<()> {
+        unsafe { targets::move_pointer(self.dpy, p) };
+        Ok(())
+    }
+
+    fn position(&mut self) -> Option<Point> {
+        unsafe { targets::pointer_position(self.dpy) }
+    }
+
+    fn scroll(&mut self, dx: i32, dy: i32) -> Result<()> {
+        // Core X scrolls with button clicks: 4 up, 5 down, 6 left, 7 right
+        let steps = [(dy, 4, 5), (dx, 6, 7)];
+        for (n, back, fwd) in steps {
+            let b = if n < 0 { back } else { fwd };
+            for _ in 0..n.unsigned_abs() {
+                self.button(b, true);
+                self.button(b, false);
+            }
+        }
+        Ok(())
+    }
+
+    fn key(&mut self, key: &str, down: bool) -> Result<()> {
+        let kc = match self.keycodes.get(key) {
+            Some(&kc) => kc,
+            None => {
+                let kc = crate::keysym_to_keycode(self.dpy, key)?;
+                self.keycodes.insert(key.to_string(), kc);
+                kc
+            }
+        };
+        unsafe { XTestFakeKeyEvent(self.dpy, kc, down as i32, CurrentTime) };
+        Ok(())
+    }
+
+    fn flush(&mut self) {
+        unsafe { XFlush(self.dpy) };
+    }
+}
+
+impl Drop for XTest {
+    fn drop(&mut self) {
+        unsafe { XCloseDisplay(self.dpy) };
+    }
+}
+
+// ---------- uinput ----------
+
+/// Name of the uinput device, so our own input can be told apart.
+pub const DEVICE_NAME: &str = "x11-autoclicker virtual input";
+
+// linux/input-event-codes.h and linux/uinput.h
+const EV_SYN: u16 = 0x00;
+const EV_KEY: u16 = 0x01;
+const EV_REL: u16 = 0x02;
+const EV_ABS: u16 = 0x03;
+const SYN_REPORT: u16 = 0;
+const REL_HWHEEL: u16 = 0x06;
+const REL_WHEEL: u16 = 0x08;
+const REL_WHEEL_HI_RES: u16 = 0x0b;
+const REL_HWHEEL_HI_RES: u16 = 0x0c;
+/// High-resolution wheel units per tick.
+const HI_RES_TICK: i32 = 120;
+const ABS_X: u16 = 0x00;
+const ABS_Y: u16 = 0x01;
+const BUS_VIRTUAL: u16 = 0x06;
+const UI_SET_EVBIT: libc::c_ulong = 0x4004_5564;
+const UI_SET_KEYBIT: libc::c_ulong = 0x4004_5565;
+const UI_SET_RELBIT: libc::c_ulong = 0x4004_5566;
+const UI_SET_ABSBIT: libc::c_ulong = 0x4004_5567;
+const UI_DEV_CREATE: libc::c_ulong = 0x5501;
+const UI_DEV_DESTROY: libc::c_ulong = 0x5502;
+
+/// X button number to evdev BTN_* code; 4..7 are wheel steps instead.
+fn evdev_button(button: u32) -> Option<u16> {
+    Some(match button {
+        1 => 0x110, // BTN_LEFT
+        2 => 0x112, // BTN_MIDDLE
+        3 => 0x111, // BTN_RIGHT
+        8 => 0x113, // BTN_SIDE (back)
+        9 => 0x114, // BTN_EXTRA (forward)
+        _ => return None,
+    })
+}
+
+/// Keysym names the uinput backend can type, with their evdev KEY_* codes.
+/// Without the X server there is no keymap to ask, so this is a US layout.
+fn evdev_key(name: &str) -> Option<u16> {
+    let lower = name.to_ascii_lowercase();
+    if lower.len() == 1 {
+        let c = lower.as_bytes()[0];
+        return KEY_ROWS
+            .iter()
+            .find_map(|(row, first)| row.bytes().position(|b| b == c).map(|i| first + i as u16));
+    }
+    if let Some(n) = name.strip_prefix('F').and_then(|n| n.parse::<u16>().ok()) {
+        return match n {
+            1..=10 => Some(58 + n),
+            11 | 12 => Some(76 + n),
+            _ => None,
+        };
+    }
+    NAMED_KEYS.iter().find(|(k, _)| *k == name).map(|&(_, code)| code)
+}
+
+/// Letter and digit rows: the characters and the code of the first one.
+const KEY_ROWS: [(&str, u16); 4] = [("1234567890", 2), ("qwertyuiop", 16), ("asdfghjkl", 30), ("zxcvbnm", 44)];
+
+const NAMED_KEYS: [(&str, u16); 23] = [
+    ("Escape", 1),
+    ("minus", 12),
+    ("equal", 13),
+    ("BackSpace", 14),
+    ("Tab", 15),
+    ("Return", 28),
+    ("Control_L", 29),
+    ("Shift_L", 42),
+    ("Alt_L", 56),
+    ("space", 57),
+    ("Super_L", 125),
+    ("Home", 102),
+    ("Up", 103),
+    ("Prior", 104),
+    ("Page_Up", 104),
+    ("Left", 105),
+    ("Right", 106),
+    ("End", 107),
+    ("Down", 108),
+    ("Next", 109),
+    ("Page_Down", 109),
+    ("Insert", 110),
+    ("Delete", 111),
+];
+
+/// Every code evdev_key() can return; the device must announce them all,
+/// or the kernel drops their events.
+fn evdev_keys() -> impl Iterator<Item = u16> {
+    let rows = KEY_ROWS.iter().flat_map(|(row, first)| (0..row.len() as u16).map(move |i| first + i));
+    let fkeys = (1..=12).map(|n| if n <= 10 { 58 + n } else { 76 + n });
+    rows.chain(fkeys).chain(NAMED_KEYS.iter().map(|&(_, code)| code))
+}
+
+/// Screen size from the X server, if there is one, for absolute moves.
+fn x_screen_size() -> Option<(i32, i32)> {
+    unsafe {
+        let dpy = XOpenDisplay(ptr::null());
+        if dpy.is_null() {
+            return None;
+        }
+        let screen = XDefaultScreen(dpy);
+        let size = (XDisplayWidth(dpy, screen), XDisplayHeight(dpy, screen));
+        XCloseDisplay(dpy);
+        Some(size)
+    }
+}
+
+/// A virtual mouse + keyboard on /dev/uinput (needs write access, e.g. via
+/// the `input` group or a udev rule).
+pub struct UInput {
+    file: File,
+    /// Screen size the absolute axes span; None without X.
+    screen: Option<(i32, i32)>,
+    /// High-resolution scrolling not yet sent as whole ticks, (x, y).
+    partial: (i32, i32),
+}
+
+impl UInput {
+    /// Give the compositor / X server time to pick up the new device, or
+    /// the first events are lost.
+    const SETTLE: Duration = Duration::from_millis(200);
+
+    pub fn new() -> Result<Self> {
+        let file = OpenOptions::new()
+            .write(true)
+            .custom_flags(libc::O_NONBLOCK)
+            .open("/dev/uinput")
+            .context("uinput: can't open /dev/uinput (is the module loaded and writable?)")?;
+        let screen = x_screen_size();
+        let fd = file.as_raw_fd();
+
+        let ioctl = |req: libc::c_ulong, arg: libc::c_int| -> Result<()> {
+            if unsafe { libc::ioctl(fd, req as _, arg) } < 0 {
+                return Err(std::io::Error::last_os_error()).context("uinput: device setup failed");
+            }
+            Ok(())
+        };
+        for ev in [EV_SYN, EV_KEY, EV_REL] {
+            ioctl(UI_SET_EVBIT, ev as _)?;
+        }
+        for b in [1, 2, 3, 8, 9].into_iter().filter_map(evdev_button) {
+            ioctl(UI_SET_KEYBIT, b as _)?;
+        }
+        for k in evdev_keys() {
+            ioctl(UI_SET_KEYBIT, k as _)?;
+        }
+        for rel in [REL_WHEEL, REL_HWHEEL, REL_WHEEL_HI_RES, REL_HWHEEL_HI_RES] {
+            ioctl(UI_SET_RELBIT, rel as _)?;
+        }
+
+        let mut dev: libc::uinput_user_dev = unsafe { std::mem::zeroed() };
+        let name = CString::new(DEVICE_NAME).unwrap();
+        for (dst, src) in dev.name.iter_mut().zip(name.as_bytes()) {
+            *dst = *src as libc::c_char;
+        }
+        dev.id.bustype = BUS_VIRTUAL;
+        dev.id.vendor = 0x1209;
+        dev.id.product = 0xac11;
+        dev.id.version = 1;
+        if let Some((w, h)) = screen {
+            ioctl(UI_SET_EVBIT, EV_ABS as _)?;
+            for (axis, size) in [(ABS_X, w), (ABS_Y, h)] {
+                ioctl(UI_SET_ABSBIT, axis as _)?;
+                dev.absmax[axis as usize] = size - 1;
+            }
+        }
+
+        let bytes = unsafe {
+            std::slice::from_raw_parts(&dev as *const _ as *const u8, std::mem::size_of::<libc::uinput_user_dev>())
+        };
+        (&file).write_all(bytes).context("uinput: device setup failed")?;
+        ioctl(UI_DEV_CREATE, 0)?;
+        thread::sleep(Self::SETTLE);
+        Ok(Self { file, screen, partial: (0, 0) })
+    }
+
+    fn emit(&mut self, type_: u16, code: u16, value: i32) -> Result<()> {
+        let mut ev: libc::input_event = unsafe { std::mem::zeroed() };
+        ev.type_ = type_;
+        ev.code = code;
+        ev.value = value;
+        let bytes = unsafe {
+            std::slice::from_raw_parts(&ev as *const _ as *const u8, std::mem::size_of::<libc::input_event>())
+        };
+        self.file.write_all(bytes).context("uinput: write failed")
+    }
+
+    fn sync(&mut self) -> Result<()> {
+        self.emit(EV_SYN, SYN_REPORT, 0)
+    }
+
+    fn button(&mut self, button: u32, down: bool) -> Result<()> {
+        match evdev_button(button) {
+            Some(code) => {
+                self.emit(EV_KEY, code, down as i32)?;
+                self.sync()
+            }
+            // Wheel "buttons" scroll once per press
+            None if (4..=7).contains(&button) => match (down, button) {
+                (false, _) => Ok(()),
+                (true, 4) => self.scroll(0, -1),
+                (true, 5) => self.scroll(0, 1),
+                (true, 6) => self.scroll(-1, 0),
+                (true, _) => self.scroll(1, 0),
+            },
+            None => bail!("uinput: no mouse button {button}"),
+        }
+    }
+}
+
+impl ClickBackend for UInput {
+    fn press(&mut self, button: u32) -> Result<()> {
+        self.button(button, true)
+    }
+
+    fn release(&mut self, button: u32) -> Result<()> {
+        self.button(button, false)
+    }
+
+    fn move_to(&mut self, p: Point) -> Result<()> {
+        if self.screen.is_none() {
+            bail!("uinput: moving the pointer needs the screen size from X11");
+        }
+        self.emit(EV_ABS, ABS_X, p.x)?;
+        self.emit(EV_ABS, ABS_Y, p.y)?;
+        self.sync()
+    }
+
+    fn position(&mut self) -> Option<Point> {
+        None
+    }
+
+    fn scroll(&mut self, dx: i32, dy: i32) -> Result<()> {
+        self.scroll_hi_res(dx * HI_RES_TICK, dy * HI_RES_TICK)
+    }
+
+    fn scroll_hi_res(&mut self, dx: i32, dy: i32) -> Result<()> {
+        // Like a real high-resolution wheel: the hi-res axes carry every
+        // step, the classic ones a tick each time 120 units add up.
+        // evdev wheels count positive away from the user (= up).
+        self.partial.0 += dx;
+        self.partial.1 += dy;
+        let ticks = (self.partial.0 / HI_RES_TICK, self.partial.1 / HI_RES_TICK);
+        self.partial.0 %= HI_RES_TICK;
+        self.partial.1 %= HI_RES_TICK;
+        if dy != 0 {
+            self.emit(EV_REL, REL_WHEEL_HI_RES, -dy)?;
+        }
+        if ticks.1 != 0 {
+            self.emit(EV_REL, REL_WHEEL, -ticks.1)?;
+        }
+        if dx != 0 {
+            self.emit(EV_REL, REL_HWHEEL_HI_RES, dx)?;
+        }
+        if ticks.0 != 0 {
+            self.emit(EV_REL, REL_HWHEEL, ticks.0)?;
+        }
+        self.sync()
+    }
+
+    fn key(&mut self, key: &str, down: bool) -> Result<()> {
+        let Some(code) = evdev_key(key) else {
+            bail!("uinput: can't type key '{key}'");
+        };
+        self.emit(EV_KEY, code, down as i32)?;
+        self.sync()
+    }
+}
+
+impl Drop for UInput {
+    fn drop(&mut self) {
+        unsafe { libc::ioctl(self.file.as_raw_fd(), UI_DEV_DESTROY as _) };
+    }
+}
+
+// ---------- SendEvent ----------
+
+/// Clicks one window with XSendEvent. The pointer stays where it is and the
+/// window doesn't need focus, but the events carry the send_event flag and
+/// some applications ignore them.
+pub struct SendEvent {
+    dpy: *mut Display,
+    root: Window,
+    window: Window,
+    /// Where to click, relative to `window`.
+    at: Point,
+    /// Set by move_to() for the next click only.
+    override_at: Option<Point>,
+    /// Buttons and modifier keys currently held, as a core event state
+    /// mask.
+    held: u32,
+}
+
+impl SendEvent {
+    pub fn new(w: &TargetWindow) -> Result<Self> {
+        let dpy = unsafe { XOpenDisplay(ptr::null()) };
+        if dpy.is_null() {
+            bail!("Window: failed to open X display");
+        }
+        let root = unsafe { XDefaultRootWindow(dpy) };
+        Ok(Self { dpy, root, window: w.id as Window, at: w.at, override_at: None, held: 0 })
+    }
+
+    /// The deepest child of `window` under `at`, with `at` in its
+    /// coordinates; toolkits often handle clicks in a subwindow.
+    unsafe fn target(&self, at: Point) -> (Window, Point) {
+        let (mut w, mut p) = (self.window, at);
+        // Bounded in case the tree changes under us
+        for _ in 0..32 {
+            let (mut x, mut y, mut child) = (0, 0, 0);
+            XTranslateCoordinates(self.dpy, w, w, p.x, p.y, &mut x, &mut y, &mut child);
+            if child == 0 {
+                break;
+            }
+            XTranslateCoordinates(self.dpy, w, child, p.x, p.y, &mut x, &mut y, &mut child);
+            w = child;
+            p = Point { x, y };
+        }
+        (w, p)
+    }
+
+    /// Send `ev` to `w`, failing if the window is gone.
+    unsafe fn send(&self, w: Window, mask: i64, ev: &mut XEvent) -> Result<()> {
+        if self.window == 0 {
+            bail!("Window: no target window picked");
+        }
+        let errors = xerror::trap(self.dpy, || {
+            XSendEvent(self.dpy, w, True, mask, ev);
+        });
+        if errors.iter().any(|e| e.error_code == BadWindow) {
+            bail!("Window: 0x{:x} was closed; pick it again", self.window);
+        }
+        Ok(())
+    }
+
+    fn button(&mut self, button: u32, down: bool) -> Result<()> {
+        let at = self.override_at.unwrap_or(self.at);
+        unsafe {
+            let (target, p) = self.target(at);
+            let (mut x_root, mut y_root, mut child) = (0, 0, 0);
+            XTranslateCoordinates(self.dpy, self.window, self.root, at.x, at.y, &mut x_root, &mut y_root, &mut child);
+            let mut ev: XEvent = std::mem::zeroed();
+            ev.button = XButtonEvent {
+                type_: if down { ButtonPress } else { ButtonRelease },
+                serial: 0,
+                send_event: True,
+                display: self.dpy,
+                window: target,
+                root: self.root,
+                subwindow: 0,
+                time: CurrentTime,
+                x: p.x,
+                y: p.y,
+                x_root,
+                y_root,
+                // Like the server: the state from before this event
+                state: self.held,
+                button,
+                same_screen: True,
+            };
+            let mask = if down { ButtonPressMask } else { ButtonReleaseMask };
+            self.send(target, mask, &mut ev)?;
+        }
+        if down {
+            self.held |= bit_for(button);
+        } else {
+            self.held &= !bit_for(button);
+        }
+        Ok(())
+    }
+}
+
+/// The state bit (ShiftMask..Mod5Mask) the server maps `keycode` to, if
+/// it is a modifier key.
+unsafe fn modifier_bit(dpy: *mut Display, keycode: u32) -> u32 {
+    let map = XGetModifierMapping(dpy);
+    if map.is_null() {
+        return 0;
+    }
+    let per = (*map).max_keypermod as usize;
+    let codes = std::slice::from_raw_parts((*map).modifiermap, 8 * per);
+    let bit = codes.iter().position(|&kc| kc != 0 && kc as u32 == keycode).map_or(0, |i| 1 << (i / per));
+    XFreeModifiermap(map);
+    bit
+}
+
+/// Button1Mask..Button5Mask; higher buttons have no state bit.
+fn bit_for(button: u32) -> u32 {
+    if (1..=5).contains(&button) {
+        Button1Mask << (button - 1)
+    } else {
+        0
+    }
+}
+
+impl ClickBackend for SendEvent {
+    fn press(&mut self, button: u32) -> Result<()> {
+        self.button(button, true)
+    }
+
+    fn release(&mut self, button: u32) -> Result<()> {
+        let r = self.button(button, false);
+        self.override_at = None;
+        r
+    }
+
+    fn move_to(&mut self, p: Point) -> Result<()> {
+        // Click targets are screen points; make them relative to the window
+        let (mut x, mut y, mut child) = (0, 0, 0);
+        unsafe { XTranslateCoordinates(self.dpy, self.root, self.window, p.x, p.y, &mut x, &mut y, &mut child) };
+        self.override_at = Some(Point { x, y });
+        Ok(())
+    }
+
+    fn position(&mut self) -> Option<Point> {
+        // The real pointer never moves, so there is nothing to restore
+        None
+    }
+
+    fn scroll(&mut self, dx: i32, dy: i32) -> Result<()> {
+        let steps = [(dy, 4, 5), (dx, 6, 7)];
+        for (n, back, fwd) in steps {
+            let b = if n < 0 { back } else { fwd };
+            for _ in 0..n.unsigned_abs() {
+                self.button(b, true)?;
+                self.button(b, false)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn key(&mut self, key: &str, down: bool) -> Result<()> {
+        let keycode = crate::keysym_to_keycode(self.dpy, key)?;
+        let at = self.override_at.unwrap_or(self.at);
+        unsafe {
+            let (target, p) = self.target(at);
+            let mut ev: XEvent = std::mem::zeroed();
+            ev.key = XKeyEvent {
+                type_: if down { KeyPress } else { KeyRelease },
+                serial: 0,
+                send_event: True,
+                display: self.dpy,
+                window: target,
+                root: self.root,
+                subwindow: 0,
+                time: CurrentTime,
+                x: p.x,
+                y: p.y,
+                x_root: 0,
+                y_root: 0,
+                // Like the server: the state from before this event
+                state: self.held,
+                keycode,
+                same_screen: True,
+            };
+            let mask = if down { KeyPressMask } else { KeyReleaseMask };
+            self.send(target, mask, &mut ev)?;
+            // Held modifiers show up in the state of later events
+            let bit = modifier_bit(self.dpy, keycode);
+            if down {
+                self.held |= bit;
+            } else {
+                self.held &= !bit;
+            }
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) {
+        unsafe { XFlush(self.dpy) };
+    }
+}
+
+impl Drop for SendEvent {
+    fn drop(&mut self) {
+        unsafe { XCloseDisplay(self.dpy) };
+    }
+}
+
+// ---------- Recording ----------
+
+/// Keeps every action in memory. Prints a summary when dropped.
+#[derive(Default)]
+pub struct Recording {
+    pub actions: Vec<Action>,
+    /// Actions not kept once `actions` reached MAX_KEPT.
+    pub dropped: u64,
+}
+
+impl Recording {
+    const MAX_KEPT: usize = 100_000;
+
+    fn record(&mut self, a: Action) -> Result<()> {
+        if self.actions.len() < Self::MAX_KEPT {
+            self.actions.push(a);
+        } else {
+            self.dropped += 1;
+        }
+        Ok(())
+    }
+
+    pub fn summary(&self) -> String {
+        let count = |f: fn(&Action) -> bool| self.actions.iter().filter(|a| f(a)).count();
+        format!(
+            "{} presses, {} releases, {} moves, {} scrolls, {} key events{}",
+            count(|a| matches!(a, Action::Press(_))),
+            count(|a| matches!(a, Action::Release(_))),
+            count(|a| matches!(a, Action::Move(..))),
+            count(|a| matches!(a, Action::Scroll { .. } | Action::ScrollHiRes { .. })),
+            count(|a| matches!(a, Action::Key { .. })),
+            if self.dropped > 0 { format!(" (+{} not kept)", self.dropped) } else { String::new() }
+        )
+    }
+}
+
+impl ClickBackend for Recording {
+    fn press(&mut self, button: u32) -> Result<()> {
+        self.record(Action::Press(button))
+    }
+
+    fn release(&mut self, button: u32) -> Result<()> {
+        self.record(Action::Release(button))
+    }
+
+    fn move_to(&mut self, p: Point) -> Result<()> {
+        self.record(Action::Move(p.x, p.y))
+    }
+
+    fn position(&mut self) -> Option<Point> {
+        None
+    }
+
+    fn scroll(&mut self, dx: i32, dy: i32) -> Result<()> {
+        self.record(Action::Scroll { dx, dy })
+    }
+
+    fn scroll_hi_res(&mut self, dx: i32, dy: i32) -> Result<()> {
+        self.record(Action::ScrollHiRes { dx, dy })
+    }
+
+    fn key(&mut self, key: &str, down: bool) -> Result<()> {
+        self.record(Action::Key { key: key.to_string(), down })
+    }
+}
+
+impl Drop for Recording {
+    fn drop(&mut self) {
+        eprintln!("[record] {}", self.summary());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recording_keeps_actions_in_order() {
+        let mut out = Recording::default();
+        let b: &mut dyn ClickBackend = &mut out;
+        b.move_to(Point { x: 10, y: 20 }).unwrap();
+        b.press(1).unwrap();
+        b.release(1).unwrap();
+        b.scroll(0, 2).unwrap();
+        b.key("space", true).unwrap();
+        assert!(b.position().is_none());
+        assert_eq!(
+            out.actions,
+            [
+                Action::Move(10, 20),
+                Action::Press(1),
+                Action::Release(1),
+                Action::Scroll { dx: 0, dy: 2 },
+                Action::Key { key: "space".to_string(), down: true },
+            ]
+        );
+        assert_eq!(out.summary(), "1 presses, 1 releases, 1 moves, 1 scrolls, 1 key events");
+    }
+
+    #[test]
+    fn recording_counts_what_it_no_longer_keeps() {
+        let mut out = Recording::default();
+        for _ in 0..Recording::MAX_KEPT + 5 {
+            out.press(1).unwrap();
+        }
+        assert_eq!(out.actions.len(), Recording::MAX_KEPT);
+        assert_eq!(out.dropped, 5);
+        assert!(out.summary().ends_with("(+5 not kept)"));
+    }
+
+    #[test]
+    fn backend_names_round_trip() {
+        for k in BackendKind::ALL {
+            assert_eq!(BackendKind::from_name(k.name()), Some(k));
+        }
+        assert_eq!(BackendKind::from_name("xlib"), None);
+    }
+}
@@ -0,0 +1,435 @@
+// ---------- Command line ----------
+// `--headless` runs hotkey_thread and click_thread without a window, for
+// scripts, SSH-forwarded displays and Xvfb rigs. Settings start from the
+// saved active profile (or --profile) and are overridden by the flags.
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::{bail, Context, Result};
+
+use crate::{
+    accel::Accel,
+    backend::BackendKind,
+    config,
+    corner::Corner,
+    focus::{FocusLock, OnLeave},
+    parse_button,
+    scroll::ScrollDirection,
+    spawn_workers,
+    stats::ClickStats,
+    takeover::Takeover,
+    wakeup::Wakeup,
+    HotkeyBackend,
+};
+
+const USAGE: &str = "\
+Usage: x11-autoclicker-gui [--headless [OPTIONS]]
+
+Without --headless the GUI is started.
+
+Headless options:
+  --profile NAME     start from this saved profile instead of the active one
+  --cps N            clicks per second (decimal)
+  --duty N           duty cycle in percent, 0..100
+  --button B         left | middle | right | 1..3, 8, 9
+  --key CHORD        press a key instead, e.g. space, e or Ctrl+c
+  --scroll DIR       scroll up | down | left | right instead of clicking
+  --scroll-rate N    scroll ticks per second (default 10)
+  --scroll-burst N   ticks sent together (default 1)
+  --smooth           high-resolution smooth scrolling (uinput backend)
+  --backend B        xtest | uinput (virtual device, also Wayland) | window (saved
+                     target window, pointer untouched) | record (dry run)
+  --hotkey KEY       hotkey, e.g. F6, Ctrl+Shift+F6 or Button8 (mouse)
+  --start-key KEY    extra key that only starts
+  --stop-key KEY     extra key that only stops
+  --kill-key KEY     emergency key: stop, release the button and exit
+  --hotkey-backend B grab (take the keys) | raw (watch via XInput2, no grab)
+  --focus-lock WHEN  pause | stop clicking while the window active when the run
+                     started isn't (use with --idle and the hotkey)
+  --takeover PX      stop when a real mouse moves more than PX pixels or a
+                     mouse button is pressed
+  --panic-corner C   stop when the pointer reaches the top-left, top-right,
+                     bottom-left or bottom-right corner (5 px zone)
+  --duration TIME    stop after TIME, e.g. 30s, 1.5m, 250ms, 1h (plain number = seconds)
+  --idle             wait for the hotkey instead of clicking immediately
+  -h, --help         show this help
+
+Stops on SIGINT/SIGTERM, releasing the button.";
+
+#[derive(Default)]
+pub struct Args {
+    pub headless: bool,
+    pub profile: Option<String>,
+    pub cps: Option<f64>,
+    pub duty: Option<f64>,
+    pub button: Option<String>,
+    pub key: Option<String>,
+    pub scroll: Option<ScrollDirection>,
+    pub scroll_rate: Option<f64>,
+    pub scroll_burst: Option<u32>,
+    pub smooth: bool,
+    pub backend: Option<BackendKind>,
+    pub hotkey: Option<String>,
+    pub start_key: Option<String>,
+    pub stop_key: Option<String>,
+    pub kill_key: Option<String>,
+    pub hotkey_backend: Option<HotkeyBackend>,
+    pub focus_lock: Option<OnLeave>,
+    pub takeover: Option<f64>,
+    pub panic_corner: Option<Corner>,
+    pub duration: Option<Duration>,
+    pub idle: bool,
+}
+
+/// Parse command-line arguments. Ok(None) if help was printed.
+pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Option<Args>> {
+    let mut out = Args::default();
+    let mut headless_only = None;
+    let mut it = args.into_iter();
+
+    while let Some(arg) = it.next() {
+        // Accept both `--flag value` and `--flag=value`
+        let (flag, inline) = match arg.split_once('=') {
+            Some((f, v)) if f.starts_with("--") => (f.to_string(), Some(v.to_string())),
+            _ => (arg.clone(), None),
+        };
+        let mut value = || {
+            inline
+                .clone()
+                .or_else(|| it.next())
+                .with_context(|| format!("{flag} needs a value"))
+        };
+
+        match flag.as_str() {
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                return Ok(None);
+            }
+            "--headless" => out.headless = true,
+            "--profile" => out.profile = Some(value()?),
+            "--cps" => {
+                let v: f64 = value()?.parse().context("--cps must be a number")?;
+                if !(v > 0.0 && v.is_finite()) {
+                    bail!("--cps must be greater than 0");
+                }
+                out.cps = Some(v);
+            }
+            "--duty" => {
+                let v: f64 = value()?.parse().context("--duty must be a number")?;
+                if !(0.0..=100.0).contains(&v) {
+                    bail!("--duty must be in 0..=100");
+                }
+                out.duty = Some(v);
+            }
+            "--button" => {
+                let v = value()?;
+                parse_button(&v)?;
+                out.button = Some(v);
+            }
+            "--key" => {
+                let v = Accel::validate(&value()?)?;
+                if Accel::parse(&v)?.button().is_some() {
+                    bail!("--key must be a key; use --button for mouse buttons");
+                }
+                out.key = Some(v);
+            }
+            "--scroll" => {
+                let v = value()?;
+                let d = ScrollDirection::from_name(&v).context("--scroll must be up, down, left or right")?;
+                out.scroll = Some(d);
+            }
+            "--scroll-rate" => {
+                let v: f64 = value()?.parse().context("--scroll-rate must be a number")?;
+                if !(v > 0.0 && v.is_finite()) {
+                    bail!("--scroll-rate must be greater than 0");
+                }
+                out.scroll_rate = Some(v);
+            }
+            "--scroll-burst" => {
+                let v: u32 = value()?.parse().context("--scroll-burst must be a whole number")?;
+                if v == 0 {
+                    bail!("--scroll-burst must be at least 1");
+                }
+                out.scroll_burst = Some(v);
+            }
+            "--smooth" => out.smooth = true,
+            "--backend" => {
+                let v = value()?;
+                let kind = BackendKind::from_name(&v).context("--backend must be xtest, uinput, window or record")?;
+                out.backend = Some(kind);
+            }
+            "--hotkey" => out.hotkey = Some(value()?),
+            "--start-key" => out.start_key = Some(value()?),
+            "--stop-key" => out.stop_key = Some(value()?),
+            "--kill-key" => out.kill_key = Some(value()?),
+            "--hotkey-backend" => {
+                out.hotkey_backend = Some(match value()?.as_str() {
+                    "grab" => HotkeyBackend::Grab,
+                    "raw" => HotkeyBackend::Raw,
+                    _ => bail!("--hotkey-backend must be grab or raw"),
+                });
+            }
+            "--focus-lock" => {
+                out.focus_lock = Some(match value()?.as_str() {
+                    "pause" => OnLeave::Pause,
+                    "stop" => OnLeave::Stop,
+                    _ => bail!("--focus-lock must be pause or stop"),
+                });
+            }
+            "--takeover" => {
+                let v: f64 = value()?.parse().context("--takeover must be a number of pixels")?;
+                if !(v > 0.0 && v.is_finite()) {
+                    bail!("--takeover must be greater than 0");
+                }
+                out.takeover = Some(v);
+            }
+            "--panic-corner" => {
+                let v = value()?;
+                let c = Corner::from_name(&v)
+                    .context("--panic-corner must be top-left, top-right, bottom-left or bottom-right")?;
+                out.panic_corner = Some(c);
+            }
+            "--duration" => {
+                let d = parse_duration(&value()?)?;
+                if d.is_zero() {
+                    bail!("--duration must be greater than 0");
+                }
+                out.duration = Some(d);
+            }
+            "--idle" => out.idle = true,
+            _ => bail!("unknown argument '{arg}'\n\n{USAGE}"),
+        }
+        if flag != "--headless" {
+            headless_only.get_or_insert(flag);
+        }
+    }
+
+    if let (false, Some(flag)) = (out.headless, headless_only) {
+        bail!("{flag} is only valid with --headless");
+    }
+    Ok(Some(out))
+}
+
+/// "30s", "1.5m", "250ms", "2h" or a plain number of seconds.
+pub fn parse_duration(text: &str) -> Result<Duration> {
+    let t = text.trim();
+    let split = t.find(|c: char| c.is_ascii_alphabetic()).unwrap_or(t.len());
+    let (num, unit) = t.split_at(split);
+    let n: f64 = num
+        .trim()
+        .parse()
+        .with_context(|| format!("invalid duration '{text}'"))?;
+    let secs = match unit {
+        "" | "s" => n,
+        "ms" => n / 1000.0,
+        "m" | "min" => n * 60.0,
+        "h" => n * 3600.0,
+        _ => bail!("invalid duration unit in '{text}' (use ms, s, m or h)"),
+    };
+    Duration::try_from_secs_f64(secs).with_context(|| format!("invalid duration '{text}'"))
+}
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn on_signal(_: libc::c_int) {
+    INTERRUPTED.store(true, Ordering::SeqCst);
+}
+
+pub fn run_headless(args: Args) -> Result<()> {
+    // Saved profiles are the base; a broken config is fatal here since
+    // there is nobody to show a warning to.
+    let mut profiles = config::load()?.unwrap_or_default();
+    if let Some(name) = &args.profile {
+        if profiles.index_of(name).is_none() {
+            bail!("no profile named '{name}'");
+        }
+        profiles.active = name.clone();
+    }
+    let mut s = profiles.active().settings.clone();
+    if let Some(v) = args.cps {
+        s.cps = v;
+    }
+    if let Some(v) = args.duty {
+        s.duty = v;
+    }
+    if let Some(v) = args.button {
+        s.button_name = v;
+    }
+    if let Some(v) = args.key {
+        s.press_key = v;
+    }
+    if let Some(v) = args.scroll {
+        s.scroll.enabled = true;
+        s.scroll.direction = v;
+    }
+    if let Some(v) = args.scroll_rate {
+        s.scroll.rate = v;
+    }
+    if let Some(v) = args.scroll_burst {
+        s.scroll.burst = v;
+    }
+    if args.smooth {
+        s.scroll.smooth = true;
+    }
+    if let Some(v) = args.backend {
+        s.click_backend = v;
+    }
+    if let Some(v) = args.hotkey {
+        s.hotkey = v;
+    }
+    if let Some(v) = args.start_key {
+        s.start_hotkey = v;
+    }
+    if let Some(v) = args.stop_key {
+        s.stop_hotkey = v;
+    }
+    if let Some(v) = args.kill_key {
+        s.kill_hotkey = v;
+    }
+    if let Some(v) = args.hotkey_backend {
+        s.hotkey_backend = v;
+    }
+    if let Some(v) = args.takeover {
+        s.takeover = Takeover { enabled: true, distance: v, on_button: true };
+    }
+    if let Some(v) = args.panic_corner {
+        s.panic_corner.enabled = true;
+        s.panic_corner.corner = v;
+    }
+    if let Some(v) = args.focus_lock {
+        s.focus_lock = FocusLock { enabled: true, on_leave: v };
+    }
+
+    unsafe {
+        libc::signal(libc::SIGINT, on_signal as *const () as libc::sighandler_t);
+        libc::signal(libc::SIGTERM, on_signal as *const () as libc::sighandler_t);
+    }
+
+    let what = if s.scroll.enabled {
+        format!("scrolling {} at {} ticks/s", s.scroll.direction.name(), s.scroll.rate)
+    } else if !s.press_key.is_empty() {
+        format!("{} CPS, {}% duty, key {}", s.cps, s.duty, s.press_key)
+    } else {
+        format!("{} CPS, {}% duty, button {}", s.cps, s.duty, s.button_name)
+    };
+    eprintln!(
+        "[headless] {what} via {}, hotkey {}{}",
+        s.click_backend.name(),
+        s.hotkey,
+        args.duration.map_or(String::new(), |d| format!(", for {d:?}"))
+    );
+
+    let running = Arc::new(AtomicBool::new(!args.idle));
+    let should_exit = Arc::new(AtomicBool::new(false));
+    let settings = Arc::new(Mutex::new(s));
+    let profiles = Arc::new(Mutex::new(profiles));
+    let stats = Arc::new(Mutex::new(ClickStats::default()));
+    // Worker events already go to stderr; nothing else reads them here
+    let (events, _) = mpsc::channel();
+    let wakeup = Arc::new(Wakeup::new()?);
+    let (hotkey, click, takeover) = spawn_workers(&running, &should_exit, &settings, &profiles, &stats, &events, &wakeup);
+
+    let started = Instant::now();
+    let mut crashed = false;
+    // should_exit is also set by the kill hotkey
+    while !INTERRUPTED.load(Ordering::SeqCst) && !should_exit.load(Ordering::SeqCst) {
+        if args.duration.is_some_and(|d| started.elapsed() >= d) {
+            break;
+        }
+        // click_thread gave up (e.g. no display): nothing left to wait for
+        if click.is_finished() {
+            crashed = true;
+            break;
+        }
+        thread::sleep(Duration::from_millis(20));
+    }
+
+    // click_thread releases the button on its way out
+    running.store(false, Ordering::SeqCst);
+    should_exit.store(true, Ordering::SeqCst);
+    wakeup.notify();
+    let _ = click.join();
+    let _ = hotkey.join();
+    let _ = takeover.join();
+
+    let m = stats.lock().unwrap().summary();
+    eprintln!(
+        "[headless] stopped after {:.3} s: {} clicks{}",
+        started.elapsed().as_secs_f64(),
+        m.clicks,
+        m.cps.map_or(String::new(), |c| format!(", {c:.6} CPS measured"))
+    );
+    if crashed {
+        bail!("click thread exited unexpectedly");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Result<Args> {
+        Ok(parse(list.iter().map(|a| a.to_string()))?.expect("no help requested"))
+    }
+
+    #[test]
+    fn duration_units() {
+        let ms = |text| parse_duration(text).unwrap().as_millis();
+        assert_eq!(ms("250ms"), 250);
+        assert_eq!(ms("30s"), 30_000);
+        assert_eq!(ms("30"), 30_000);
+        assert_eq!(ms("1.5m"), 90_000);
+        assert_eq!(ms("2min"), 120_000);
+        assert_eq!(ms(" 1h "), 3_600_000);
+        for bad in ["", "s", "10d", "1.5.2s", "-1s", "inf"] {
+            assert!(parse_duration(bad).is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn flags_take_values_both_ways() {
+        let a = args(&["--headless", "--cps=12.5", "--duty", "40", "--hotkey=Ctrl+F6", "--duration=2s"]).unwrap();
+        assert!(a.headless);
+        assert_eq!(a.cps, Some(12.5));
+        assert_eq!(a.duty, Some(40.0));
+        assert_eq!(a.hotkey.as_deref(), Some("Ctrl+F6"));
+        assert_eq!(a.duration, Some(Duration::from_secs(2)));
+        assert!(args(&["--headless", "--cps"]).is_err());
+    }
+
+    #[test]
+    fn options_need_headless() {
+        assert!(!args(&[]).unwrap().headless);
+        let e = args(&["--cps", "5"]).err().unwrap().to_string();
+        assert_eq!(e, "--cps is only valid with --headless");
+        // --headless may come last
+        assert!(args(&["--idle", "--headless"]).unwrap().idle);
+        assert!(args(&["--bogus"]).is_err());
+    }
+
+    #[test]
+    fn rejects_zero_and_negative_values() {
+        for bad in [
+            ["--cps", "0"],
+            ["--cps", "-3"],
+            ["--duty", "-1"],
+            ["--duty", "101"],
+            ["--scroll-rate", "0"],
+            ["--scroll-burst", "0"],
+            ["--scroll-burst", "-1"],
+            ["--takeover", "-5"],
+            ["--duration", "0s"],
+            ["--duration", "-2s"],
+        ] {
+            assert!(args(&["--headless", bad[0], bad[1]]).is_err(), "{bad:?}");
+        }
+    }
+}
@@ -0,0 +1,69 @@
+// ---------- Config file ----------
+// Profiles live in $XDG_CONFIG_HOME/x11-autoclicker-gui/config.toml
+// (falling back to ~/.config). A missing file means defaults; a broken one
+// is reported to the caller, which moves it aside with set_aside() before
+// saving over it.
+
+use std::{fs, path::PathBuf};
+
+use anyhow::{Context, Result};
+
+use crate::profiles::Profiles;
+
+const APP_DIR: &str = "x11-autoclicker-gui";
+
+pub fn config_dir() -> Result<PathBuf> {
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".config")))
+        .context("neither XDG_CONFIG_HOME nor HOME is set")?;
+    Ok(base.join(APP_DIR))
+}
+
+pub fn config_path() -> Result<PathBuf> {
+    Ok(config_dir()?.join("config.toml"))
+}
+
+/// Load saved profiles. Ok(None) if there is no config file yet.
+pub fn load() -> Result<Option<Profiles>> {
+    let path = config_path()?;
+    let text = match fs::read_to_string(&path) {
+        Ok(t) => t,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e).with_context(|| format!("reading {}", path.display())),
+    };
+    let parse = || -> Result<Profiles> {
+        let value: toml::Value = toml::from_str(&text)?;
+        if value.get("profile").is_some() {
+            Ok(value.try_into::<Profiles>()?.normalized())
+        } else {
+            // Older files hold a single flat Settings table
+            Ok(Profiles::from_settings(value.try_into()?))
+        }
+    };
+    parse().map(Some).with_context(|| format!("parsing {}", path.display()))
+}
+
+/// Rename the config file to config.toml.bad, so a file that failed to load
+/// isn't lost to the next save. Returns the new path.
+pub fn set_aside() -> Result<PathBuf> {
+    let path = config_path()?;
+    let bad = path.with_extension("toml.bad");
+    fs::rename(&path, &bad).with_context(|| format!("moving {} to {}", path.display(), bad.display()))?;
+    Ok(bad)
+}
+
+pub fn save(profiles: &Profiles) -> Result<()> {
+    let path = config_path()?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).with_context(|| format!("creating {}", dir.display()))?;
+    }
+    let text = toml::to_string_pretty(profiles).context("serializing profiles")?;
+
+    // Write-then-rename so a crash never leaves a truncated config behind
+    let tmp = path.with_extension("toml.tmp");
+    fs::write(&tmp, text).with_context(|| format!("writing {}", tmp.display()))?;
+    fs::rename(&tmp, &path).with_context(|| format!("replacing {}", path.display()))?;
+    Ok(())
+}
@@ -0,0 +1,101 @@
+// ---------- Panic corner ----------
+// Pushing the pointer into a chosen corner of the screen stops clicking at
+// once, releasing a held button, like PyAutoGUI's fail-safe. click_thread
+// checks before every press and while a button is down.
+
+use std::ptr;
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use x11::xlib::*;
+
+use crate::targets::{self, Point};
+
+#[derive(Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Corner {
+    #[default]
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl Corner {
+    pub const ALL: [Corner; 4] = [Corner::TopLeft, Corner::TopRight, Corner::BottomLeft, Corner::BottomRight];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Corner::TopLeft => "top-left",
+            Corner::TopRight => "top-right",
+            Corner::BottomLeft => "bottom-left",
+            Corner::BottomRight => "bottom-right",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|c| c.name() == name)
+    }
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PanicCorner {
+    pub enabled: bool,
+    pub corner: Corner,
+    /// Side of the square hot zone, in pixels.
+    pub size: i32,
+}
+
+impl Default for PanicCorner {
+    fn default() -> Self {
+        Self { enabled: false, corner: Corner::TopLeft, size: 5 }
+    }
+}
+
+impl PanicCorner {
+    /// Whether `p` is in the hot zone of a `width` x `height` screen.
+    pub fn contains(&self, p: Point, (width, height): (i32, i32)) -> bool {
+        let left = p.x < self.size;
+        let right = p.x >= width - self.size;
+        let top = p.y < self.size;
+        let bottom = p.y >= height - self.size;
+        match self.corner {
+            Corner::TopLeft => top && left,
+            Corner::TopRight => top && right,
+            Corner::BottomLeft => bottom && left,
+            Corner::BottomRight => bottom && right,
+        }
+    }
+}
+
+/// Reads the real pointer on its own connection, whatever the click
+/// backend (the window and record backends don't move it).
+pub struct CornerWatch {
+    dpy: *mut Display,
+    screen: (i32, i32),
+}
+
+impl CornerWatch {
+    pub fn new() -> Result<Self> {
+        let dpy = unsafe { XOpenDisplay(ptr::null()) };
+        if dpy.is_null() {
+            bail!("Panic corner: failed to open X display");
+        }
+        let screen = unsafe {
+            let s = XDefaultScreen(dpy);
+            (XDisplayWidth(dpy, s), XDisplayHeight(dpy, s))
+        };
+        Ok(Self { dpy, screen })
+    }
+
+    pub fn hit(&self, c: &PanicCorner) -> bool {
+        unsafe { targets::pointer_position(self.dpy) }.is_some_and(|p| c.contains(p, self.screen))
+    }
+}
+
+impl Drop for CornerWatch {
+    fn drop(&mut self) {
+        unsafe { XCloseDisplay(self.dpy) };
+    }
+}
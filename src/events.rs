@@ -0,0 +1,117 @@
+// ---------- Worker events ----------
+// hotkey_thread and click_thread report status changes and problems over
+// a channel; the GUI shows them, and every event is also logged to stderr
+// as before.
+
+use std::{fmt, sync::mpsc};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Worker {
+    Hotkey,
+    Click,
+    Takeover,
+}
+
+impl Worker {
+    fn tag(self) -> &'static str {
+        match self {
+            Worker::Hotkey => "hotkey",
+            Worker::Click => "click",
+            Worker::Takeover => "takeover",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum WorkerError {
+    /// The thread could not start or stopped with an error.
+    Fatal(String),
+    /// A hotkey string that doesn't parse or has no key on this keyboard.
+    BadHotkey { key: String, reason: String },
+    /// XGrabKey failed with BadAccess: another client owns the key in the
+    /// listed lock states. `suggestions` are keys that are still free.
+    GrabConflict {
+        key: String,
+        variants: Vec<String>,
+        suggestions: Vec<String>,
+    },
+    BadButton(String),
+    BadKey(String),
+    BadStopTime(String),
+    /// The click backend could not be opened or failed to inject.
+    Backend(String),
+    /// The window lock can't tell which window is active.
+    FocusLock(String),
+    /// The pointer position can't be read for the panic corner.
+    PanicCorner(String),
+    /// The raw hotkey backend was chosen but the server lacks XI 2.2.
+    NoXInput2,
+}
+
+impl fmt::Display for WorkerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WorkerError::Fatal(e) => write!(f, "{e}"),
+            WorkerError::BadHotkey { key, reason } => write!(f, "hotkey '{key}' not bound: {reason}"),
+            WorkerError::GrabConflict { key, variants, suggestions } => {
+                write!(f, "hotkey '{key}' is already grabbed by another application")?;
+                if variants.len() < crate::MOD_VARIANTS.len() {
+                    write!(f, " (with {})", variants.join(", "))?;
+                }
+                if !suggestions.is_empty() {
+                    write!(f, "; free keys: {}", suggestions.join(", "))?;
+                }
+                Ok(())
+            }
+            WorkerError::BadButton(e) => write!(f, "{e}, clicking button 1 instead"),
+            WorkerError::BadKey(e) => write!(f, "{e}, clicking the mouse button instead"),
+            WorkerError::BadStopTime(e) => write!(f, "{e}, ignoring the stop time"),
+            WorkerError::Backend(e) => write!(f, "{e}; clicking stopped"),
+            WorkerError::FocusLock(e) => write!(f, "{e}; clicking stopped"),
+            WorkerError::PanicCorner(e) => write!(f, "{e}; clicking stopped"),
+            WorkerError::NoXInput2 => write!(f, "XInput 2.2 is not available, grabbing hotkeys instead"),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum Event {
+    Status(Worker, String),
+    Error(Worker, WorkerError),
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Event::Status(w, msg) => write!(f, "[{}] {msg}", w.tag()),
+            Event::Error(w, e) => write!(f, "[{}] error: {e}", w.tag()),
+        }
+    }
+}
+
+/// Sending half handed to a worker thread.
+#[derive(Clone)]
+pub struct Reporter {
+    tx: mpsc::Sender<Event>,
+    from: Worker,
+}
+
+impl Reporter {
+    pub fn new(tx: mpsc::Sender<Event>, from: Worker) -> Self {
+        Self { tx, from }
+    }
+
+    fn send(&self, ev: Event) {
+        eprintln!("{ev}");
+        // Nobody listening (e.g. headless) is fine
+        let _ = self.tx.send(ev);
+    }
+
+    pub fn status(&self, msg: impl Into<String>) {
+        self.send(Event::Status(self.from, msg.into()));
+    }
+
+    pub fn error(&self, e: WorkerError) {
+        self.send(Event::Error(self.from, e));
+    }
+}
@@ -0,0 +1,107 @@
+// ---------- Window lock ----------
+// With the lock on, click_thread remembers the window that was active
+// (_NET_ACTIVE_WINDOW) when the run started and only clicks while it stays
+// active. The window manager updates that root property on every focus
+// change, so PropertyNotify tells us when to look again.
+
+use std::{os::raw::c_int, ptr, time::Duration};
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use x11::xlib::*;
+
+#[derive(Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OnLeave {
+    /// Wait until the window is active again, then carry on.
+    #[default]
+    Pause,
+    Stop,
+}
+
+#[derive(Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FocusLock {
+    pub enabled: bool,
+    pub on_leave: OnLeave,
+}
+
+/// Tracks the active window on its own display connection.
+pub struct FocusWatch {
+    dpy: *mut Display,
+    root: Window,
+    net_active: Atom,
+    active: Window,
+}
+
+impl FocusWatch {
+    pub fn new() -> Result<Self> {
+        let dpy = unsafe { XOpenDisplay(ptr::null()) };
+        if dpy.is_null() {
+            bail!("Window lock: failed to open X display");
+        }
+        let mut w = unsafe {
+            let root = XDefaultRootWindow(dpy);
+            let net_active = XInternAtom(dpy, c"_NET_ACTIVE_WINDOW".as_ptr(), False);
+            XSelectInput(dpy, root, PropertyChangeMask);
+            Self { dpy, root, net_active, active: 0 }
+        };
+        match w.read() {
+            Some(active) => w.active = active,
+            None => bail!("Window lock: the window manager doesn't publish _NET_ACTIVE_WINDOW"),
+        }
+        Ok(w)
+    }
+
+    fn read(&self) -> Option<Window> {
+        let (mut actual, mut format, mut n, mut after) = (0, 0, 0, 0);
+        let mut data = ptr::null_mut();
+        unsafe {
+            let status = XGetWindowProperty(
+                self.dpy, self.root, self.net_active, 0, 1, False, XA_WINDOW, &mut actual, &mut format, &mut n,
+                &mut after, &mut data,
+            );
+            if status != Success as c_int || data.is_null() {
+                return None;
+            }
+            // 32-bit properties come back as longs
+            let w = (n == 1).then(|| *(data as *const Window));
+            XFree(data as *mut _);
+            w
+        }
+    }
+
+    /// The active window (0 for none), after handling queued changes.
+    pub fn active(&mut self) -> Window {
+        let mut changed = false;
+        unsafe {
+            while XPending(self.dpy) > 0 {
+                let mut ev: XEvent = std::mem::zeroed();
+                XNextEvent(self.dpy, &mut ev);
+                if ev.get_type() == PropertyNotify && ev.property.atom == self.net_active {
+                    changed = true;
+                }
+            }
+        }
+        if changed {
+            self.active = self.read().unwrap_or(0);
+        }
+        self.active
+    }
+
+    /// Sleep until the active window may have changed, or `timeout`.
+    pub fn wait(&self, timeout: Duration) {
+        let mut fd = libc::pollfd {
+            fd: unsafe { XConnectionNumber(self.dpy) },
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        unsafe { libc::poll(&mut fd, 1, timeout.as_millis() as c_int) };
+    }
+}
+
+impl Drop for FocusWatch {
+    fn drop(&mut self) {
+        unsafe { XCloseDisplay(self.dpy) };
+    }
+}
@@ -0,0 +1,101 @@
+// ---------- Hotkey input widget ----------
+// A text field plus "Capture" button for one accelerator. Typed edits are
+// only committed on Enter/focus loss and only if they parse, so
+// hotkey_thread never tries to grab a half-typed key.
+
+use std::{collections::HashMap, sync::mpsc::TryRecvError};
+
+use anyhow::{bail, Result};
+use eframe::egui;
+
+use crate::{
+    accel::Accel,
+    picker::{self, Pending},
+};
+
+#[derive(Default)]
+pub struct HotkeyEditor {
+    drafts: HashMap<&'static str, String>,
+    capturing: Option<(&'static str, Pending<String>)>,
+}
+
+impl HotkeyEditor {
+    /// Draw the editor for `value`. `optional` keys may be cleared;
+    /// `keys_only` refuses mouse buttons. Returns an error message for the
+    /// caller to show, if any.
+    pub fn show(
+        &mut self,
+        ui: &mut egui::Ui,
+        id: &'static str,
+        value: &mut String,
+        optional: bool,
+        keys_only: bool,
+    ) -> Option<String> {
+        let mut error = None;
+        let draft = self.drafts.entry(id).or_insert_with(|| value.clone());
+
+        let hint = if optional { "unbound" } else { "" };
+        let resp = ui.add(egui::TextEdit::singleline(draft).desired_width(140.0).hint_text(hint));
+        if resp.lost_focus() {
+            let text = draft.trim();
+            if text.is_empty() && optional {
+                value.clear();
+            } else {
+                match check(text, keys_only) {
+                    Ok(canonical) => *value = canonical,
+                    Err(e) => error = Some(format!("{e:#}")),
+                }
+            }
+            *draft = value.clone();
+        } else if !resp.has_focus() {
+            // Follow outside changes (profile switch, capture)
+            *draft = value.clone();
+        }
+
+        match &self.capturing {
+            Some((cid, rx)) if *cid == id => {
+                ui.label(if keys_only {
+                    "Press a key… (Esc cancels)"
+                } else {
+                    "Press a key or mouse button… (Esc cancels)"
+                });
+                let done = match rx.try_recv() {
+                    Ok(Ok(Some(key))) => {
+                        match check(&key, keys_only) {
+                            Ok(key) => *value = key,
+                            Err(e) => error = Some(format!("{e:#}")),
+                        }
+                        true
+                    }
+                    Ok(Ok(None)) | Err(TryRecvError::Disconnected) => true,
+                    Ok(Err(e)) => {
+                        error = Some(format!("{e:#}"));
+                        true
+                    }
+                    Err(TryRecvError::Empty) => false,
+                };
+                if done {
+                    self.capturing = None;
+                }
+            }
+            other => {
+                let idle = other.is_none();
+                if ui.add_enabled(idle, egui::Button::new("Capture")).clicked() {
+                    self.capturing = Some((id, picker::capture_key()));
+                }
+                if optional && !value.is_empty() && ui.small_button("✖").clicked() {
+                    value.clear();
+                }
+            }
+        }
+        error
+    }
+}
+
+/// Validate an accelerator, refusing mouse buttons when `keys_only`.
+fn check(text: &str, keys_only: bool) -> Result<String> {
+    if keys_only && Accel::parse(text)?.button().is_some() {
+        bail!("'{text}' is a mouse button; only keys can be pressed here");
+    }
+    Accel::validate(text)
+}
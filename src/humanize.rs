@@ -0,0 +1,155 @@
+// ---------- Humanization ----------
+// Optional per-click randomness. Each click draws a multiplier for its
+// period and one for its press length; all distributions have mean 1 so the
+// long-run rate still converges to Settings::cps.
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use rand_distr::{Distribution as _, LogNormal, Normal};
+use serde::{Deserialize, Serialize};
+
+/// Shape of the random multiplier. Parameters are in percent of the
+/// nominal value (log-normal: sigma of ln(multiplier), in percent).
+#[derive(Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum Jitter {
+    #[default]
+    Off,
+    Uniform {
+        spread: f64,
+    },
+    Gaussian {
+        sigma: f64,
+    },
+    LogNormal {
+        sigma: f64,
+    },
+}
+
+impl Jitter {
+    /// Smallest multiplier ever applied, so a wild draw can't produce a
+    /// zero or negative interval.
+    const MIN_FACTOR: f64 = 0.05;
+
+    pub const NAMES: [&'static str; 4] = ["off", "uniform", "gaussian", "lognormal"];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Jitter::Off => "off",
+            Jitter::Uniform { .. } => "uniform",
+            Jitter::Gaussian { .. } => "gaussian",
+            Jitter::LogNormal { .. } => "lognormal",
+        }
+    }
+
+    /// Same kind of distribution by name, keeping the current width.
+    pub fn with_name(&self, name: &str) -> Self {
+        let w = self.width().unwrap_or(10.0);
+        match name {
+            "uniform" => Jitter::Uniform { spread: w },
+            "gaussian" => Jitter::Gaussian { sigma: w },
+            "lognormal" => Jitter::LogNormal { sigma: w },
+            _ => Jitter::Off,
+        }
+    }
+
+    pub fn width(&self) -> Option<f64> {
+        match *self {
+            Jitter::Off => None,
+            Jitter::Uniform { spread: w } | Jitter::Gaussian { sigma: w } | Jitter::LogNormal { sigma: w } => {
+                Some(w)
+            }
+        }
+    }
+
+    pub fn width_mut(&mut self) -> Option<&mut f64> {
+        match self {
+            Jitter::Off => None,
+            Jitter::Uniform { spread: w } | Jitter::Gaussian { sigma: w } | Jitter::LogNormal { sigma: w } => {
+                Some(w)
+            }
+        }
+    }
+
+    fn sample(&self, rng: &mut StdRng) -> f64 {
+        let f = match *self {
+            Jitter::Off => 1.0,
+            Jitter::Uniform { spread } => {
+                let s = (spread / 100.0).clamp(0.0, 1.0);
+                if s > 0.0 {
+                    rng.gen_range(1.0 - s..=1.0 + s)
+                } else {
+                    1.0
+                }
+            }
+            Jitter::Gaussian { sigma } => Normal::new(1.0, sigma.max(0.0) / 100.0)
+                .map_or(1.0, |d| d.sample(rng)),
+            Jitter::LogNormal { sigma } => {
+                // mu = -sigma²/2 gives E[multiplier] = 1
+                let s = sigma.max(0.0) / 100.0;
+                LogNormal::new(-s * s / 2.0, s).map_or(1.0, |d| d.sample(rng))
+            }
+        };
+        f.max(Self::MIN_FACTOR)
+    }
+
+    /// Range covering ~95% of multipliers (exact bounds for uniform).
+    pub fn range(&self) -> (f64, f64) {
+        const Z95: f64 = 1.959964;
+        match *self {
+            Jitter::Off => (1.0, 1.0),
+            Jitter::Uniform { spread } => {
+                let s = (spread / 100.0).clamp(0.0, 1.0);
+                (1.0 - s, 1.0 + s)
+            }
+            Jitter::Gaussian { sigma } => {
+                let s = sigma.max(0.0) / 100.0;
+                ((1.0 - Z95 * s).max(Self::MIN_FACTOR), 1.0 + Z95 * s)
+            }
+            Jitter::LogNormal { sigma } => {
+                let s = sigma.max(0.0) / 100.0;
+                let mu = -s * s / 2.0;
+                ((mu - Z95 * s).exp().max(Self::MIN_FACTOR), (mu + Z95 * s).exp())
+            }
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Humanize {
+    pub period: Jitter,
+    pub press: Jitter,
+    /// Fixed RNG seed for reproducible runs; random per run if None.
+    pub seed: Option<u32>,
+}
+
+impl Humanize {
+    pub fn is_off(&self) -> bool {
+        self.period == Jitter::Off && self.press == Jitter::Off
+    }
+}
+
+/// Per-run random source for click_thread.
+pub struct Humanizer {
+    rng: StdRng,
+}
+
+impl Humanizer {
+    pub fn new(seed: Option<u32>) -> Self {
+        let rng = match seed {
+            Some(s) => StdRng::seed_from_u64(s as u64),
+            None => StdRng::from_entropy(),
+        };
+        Self { rng }
+    }
+
+    /// Uniform index in 0..n, from the same (possibly seeded) stream.
+    pub fn index(&mut self, n: usize) -> usize {
+        self.rng.gen_range(0..n)
+    }
+
+    /// (period multiplier, press multiplier) for the next click.
+    pub fn draw(&mut self, h: &Humanize) -> (f64, f64) {
+        (h.period.sample(&mut self.rng), h.press.sample(&mut self.rng))
+    }
+}
@@ -0,0 +1,180 @@
+// ---------- Stop conditions ----------
+// A run can end after N clicks, after a wall-clock duration or at a time of
+// day. click_thread turns Limits into a RunLimit when a run starts and
+// clears `running` once it is reached.
+
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Limits {
+    pub max_clicks: Option<u64>,
+    pub max_secs: Option<f64>,
+    /// Local time of day, "HH:MM" or "HH:MM:SS".
+    pub stop_at: Option<String>,
+}
+
+impl Limits {
+    pub fn is_empty(&self) -> bool {
+        self.max_clicks.is_none() && self.max_secs.is_none() && self.stop_at.is_none()
+    }
+}
+
+/// Seconds since local midnight for "HH:MM" or "HH:MM:SS".
+pub fn parse_time_of_day(text: &str) -> Result<u32> {
+    let parts: Vec<&str> = text.trim().split(':').collect();
+    if !(2..=3).contains(&parts.len()) {
+        bail!("time of day must be HH:MM or HH:MM:SS");
+    }
+    let mut v = [0u32; 3];
+    for (i, p) in parts.iter().enumerate() {
+        v[i] = p
+            .parse()
+            .with_context(|| format!("invalid time of day '{text}'"))?;
+    }
+    if v[0] > 23 || v[1] > 59 || v[2] > 59 {
+        bail!("invalid time of day '{text}'");
+    }
+    Ok(v[0] * 3600 + v[1] * 60 + v[2])
+}
+
+/// Time from now until the next occurrence of `secs_of_day` local time.
+fn until_time_of_day(secs_of_day: u32) -> Duration {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    let t = now.as_secs() as libc::time_t;
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    unsafe { libc::localtime_r(&t, &mut tm) };
+    let current = (tm.tm_hour * 3600 + tm.tm_min * 60 + tm.tm_sec) as f64
+        + now.subsec_nanos() as f64 / 1e9;
+    let mut left = secs_of_day as f64 - current;
+    if left <= 0.0 {
+        left += 86_400.0;
+    }
+    Duration::from_secs_f64(left)
+}
+
+/// Limits resolved against the start of a run.
+#[derive(Clone)]
+pub struct RunLimit {
+    pub started: Instant,
+    pub max_clicks: Option<u64>,
+    pub deadline: Option<Instant>,
+}
+
+impl RunLimit {
+    pub fn new(limits: &Limits, started: Instant) -> Result<Self> {
+        let by_duration = limits
+            .max_secs
+            .map(|s| started + Duration::from_secs_f64(s.max(0.0)));
+        let by_clock = match &limits.stop_at {
+            // Measured from now: a run whose limits change midway is
+            // resolved again with its original start
+            Some(t) => Some(Instant::now() + until_time_of_day(parse_time_of_day(t)?)),
+            None => None,
+        };
+        let deadline = match (by_duration, by_clock) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        Ok(Self {
+            started,
+            max_clicks: limits.max_clicks,
+            deadline,
+        })
+    }
+
+    pub fn clicks_reached(&self, completed: u64) -> bool {
+        self.max_clicks.is_some_and(|n| completed >= n)
+    }
+
+    pub fn time_reached(&self, at: Instant) -> bool {
+        self.deadline.is_some_and(|d| at >= d)
+    }
+
+    /// Fraction done (0..=1) of whichever limit is closest to being hit.
+    pub fn progress(&self, completed: u64, now: Instant) -> Option<f32> {
+        let by_clicks = self
+            .max_clicks
+            .map(|n| if n == 0 { 1.0 } else { completed as f64 / n as f64 });
+        let by_time = self.deadline.map(|d| {
+            let total = d.saturating_duration_since(self.started).as_secs_f64();
+            if total <= 0.0 {
+                1.0
+            } else {
+                now.saturating_duration_since(self.started).as_secs_f64() / total
+            }
+        });
+        let p = match (by_clicks, by_time) {
+            (Some(a), Some(b)) => a.max(b),
+            (a, b) => a.or(b)?,
+        };
+        Some(p.clamp(0.0, 1.0) as f32)
+    }
+
+    pub fn remaining_clicks(&self, completed: u64) -> Option<u64> {
+        self.max_clicks.map(|n| n.saturating_sub(completed))
+    }
+
+    pub fn remaining_time(&self, now: Instant) -> Option<Duration> {
+        self.deadline.map(|d| d.saturating_duration_since(now))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn time_of_day() {
+        assert_eq!(parse_time_of_day("00:00").unwrap(), 0);
+        assert_eq!(parse_time_of_day(" 7:05 ").unwrap(), 7 * 3600 + 5 * 60);
+        assert_eq!(parse_time_of_day("23:59:59").unwrap(), 86_399);
+        for bad in ["24:00", "12:60", "12:00:60", "12:", "12:30:", ":30", "12", "1:2:3:4", "ab:cd", "-1:00"] {
+            assert!(parse_time_of_day(bad).is_err(), "{bad}");
+        }
+    }
+
+    fn limit(max_clicks: Option<u64>, max_secs: Option<f64>, started: Instant) -> RunLimit {
+        RunLimit::new(&Limits { max_clicks, max_secs, stop_at: None }, started).unwrap()
+    }
+
+    #[test]
+    fn progress_follows_the_closest_limit() {
+        let t0 = Instant::now();
+        let l = limit(Some(10), Some(100.0), t0);
+        assert_eq!(l.progress(2, t0 + Duration::from_secs(50)), Some(0.5));
+        assert_eq!(l.progress(8, t0 + Duration::from_secs(50)), Some(0.8));
+        // Overshooting stays at 1
+        assert_eq!(l.progress(20, t0 + Duration::from_secs(500)), Some(1.0));
+        assert_eq!(limit(None, None, t0).progress(5, t0), None);
+    }
+
+    #[test]
+    fn remaining() {
+        let t0 = Instant::now();
+        let l = limit(Some(10), Some(10.0), t0);
+        assert_eq!(l.remaining_clicks(3), Some(7));
+        assert_eq!(l.remaining_clicks(12), Some(0));
+        assert_eq!(l.remaining_time(t0 + Duration::from_secs(4)), Some(Duration::from_secs(6)));
+        assert_eq!(l.remaining_time(t0 + Duration::from_secs(40)), Some(Duration::ZERO));
+        assert!(!l.clicks_reached(9) && l.clicks_reached(10));
+        assert!(l.time_reached(t0 + Duration::from_secs(10)));
+        let none = limit(None, None, t0);
+        assert_eq!((none.remaining_clicks(3), none.remaining_time(t0)), (None, None));
+    }
+
+    #[test]
+    fn zero_limits_are_already_reached() {
+        let t0 = Instant::now();
+        let l = limit(Some(0), Some(0.0), t0);
+        assert_eq!(l.progress(0, t0), Some(1.0));
+        assert_eq!(l.remaining_clicks(0), Some(0));
+        assert_eq!(l.remaining_time(t0), Some(Duration::ZERO));
+        assert!(l.clicks_reached(0) && l.time_reached(t0));
+    }
+}
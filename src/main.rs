@@ -2,26 +2,105 @@ use std::{
     ptr,
     sync::{
         atomic::{AtomicBool, Ordering},
-        Arc, Mutex,
+        mpsc, Arc, Mutex,
     },
     thread,
-    time::Duration,
+    time::{Duration, Instant},
 };
 
+mod accel;
+mod backend;
+mod cli;
+mod config;
+mod corner;
+mod events;
+mod focus;
+mod hotkey_field;
+mod humanize;
+mod limits;
+mod picker;
+mod profiles;
+mod scroll;
+mod stats;
+mod takeover;
+mod targets;
+mod wakeup;
+mod xerror;
+mod xi;
+
 use anyhow::{bail, Context, Result};
 use eframe::egui;
+use serde::{Deserialize, Serialize};
 use spin_sleep::SpinSleeper;
 
+use x11::xinput2::{XI_RawButtonPress, XI_RawButtonRelease, XI_RawKeyPress, XI_RawKeyRelease};
 use x11::xlib::*;
-use x11::xtest::*;
+
+use accel::{Accel, KeyCombo, Trigger};
+use backend::{BackendKind, ClickBackend};
+use corner::{Corner, CornerWatch, PanicCorner};
+use events::{Event, Reporter, Worker, WorkerError};
+use focus::{FocusLock, FocusWatch, OnLeave};
+use hotkey_field::HotkeyEditor;
+use humanize::{Humanize, Humanizer, Jitter};
+use limits::{Limits, RunLimit};
+use profiles::Profiles;
+use scroll::{Scroll, ScrollDirection};
+use stats::{ClickStats, LATE_THRESHOLD_MS};
+use takeover::{takeover_thread, Takeover};
+use targets::{Point, TargetCycler, TargetOrder, TargetWindow, Targets};
+use wakeup::Wakeup;
 
 // ---------- Shared state ----------
-#[derive(Clone)]
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 struct Settings {
     cps: f64,            // clicks per second (decimal)
     duty: f64,           // percent 0..100 (decimal)
-    button_name: String, // "left" | "middle" | "right" | "1..9"
-    hotkey: String,      // X11 keysym string, e.g., "F6", "F8", "q"
+    button_name: String, // "left" | "middle" | "right" | "1..3" | "8" | "9"
+    press_key: String,   // key chord to press instead, e.g. "e"; empty = button
+    hotkey: String,      // keysym or ButtonN with optional modifiers, e.g., "F6", "Ctrl+Shift+q", "Button8"
+    humanize: Humanize,  // per-click random variation, off by default
+    limits: Limits,      // auto-stop conditions, none by default
+    targets: Targets,    // fixed screen points to click, off by default
+    activation: Activation,
+    burst_clicks: u64, // clicks per hotkey press in Burst mode
+    // Optional single-purpose keys next to `hotkey`; empty = unbound
+    start_hotkey: String,
+    stop_hotkey: String,
+    kill_hotkey: String, // stop, release the button and quit
+    hotkey_backend: HotkeyBackend,
+    click_backend: BackendKind, // how clicks are injected
+    window: TargetWindow,       // clicked by BackendKind::Window
+    focus_lock: FocusLock,      // only click while the start window is active
+    takeover: Takeover,         // stop when the user grabs the mouse
+    panic_corner: PanicCorner,  // stop when the pointer hits a screen corner
+    scroll: Scroll,             // turn the wheel instead of clicking
+}
+
+/// How hotkey_thread listens for the hotkeys.
+#[derive(Clone, Copy, PartialEq, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum HotkeyBackend {
+    /// XGrabKey/XGrabButton: the key is taken from other applications.
+    #[default]
+    Grab,
+    /// XInput2 raw events: observe passively, works during other
+    /// clients' keyboard grabs.
+    Raw,
+}
+
+/// What the hotkey does.
+#[derive(Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Activation {
+    /// Press to start, press again to stop.
+    #[default]
+    Toggle,
+    /// Click only while the key is held down.
+    Hold,
+    /// Each press fires `burst_clicks` clicks.
+    Burst,
 }
 
 impl Default for Settings {
@@ -30,25 +109,138 @@ impl Default for Settings {
             cps: 24.32345237573,
             duty: 36.836218324712,
             button_name: "left".to_string(),
+            press_key: String::new(),
             hotkey: "F6".to_string(),
+            humanize: Humanize::default(),
+            limits: Limits::default(),
+            targets: Targets::default(),
+            activation: Activation::Toggle,
+            burst_clicks: 10,
+            start_hotkey: String::new(),
+            stop_hotkey: String::new(),
+            kill_hotkey: String::new(),
+            hotkey_backend: HotkeyBackend::Grab,
+            click_backend: BackendKind::XTest,
+            window: TargetWindow::default(),
+            focus_lock: FocusLock::default(),
+            takeover: Takeover::default(),
+            panic_corner: PanicCorner::default(),
+            scroll: Scroll::default(),
         }
     }
 }
 
+/// Press/release split of one click period, all in seconds.
+#[derive(Clone, Copy, PartialEq)]
+struct ClickTiming {
+    period: f64,
+    on_time: f64,
+}
+
+impl ClickTiming {
+    const MIN_PRESS: f64 = 0.001; // 1 ms
+
+    fn off_time(&self) -> f64 {
+        (self.period - self.on_time).max(0.0)
+    }
+}
+
+impl Settings {
+    /// Stop conditions for a run, including the Burst click count.
+    fn run_limits(&self) -> Limits {
+        let mut l = self.limits.clone();
+        if self.activation == Activation::Burst {
+            let n = self.burst_clicks.max(1);
+            l.max_clicks = Some(l.max_clicks.map_or(n, |m| m.min(n)));
+        }
+        l
+    }
+
+    /// The X button click_thread injects; in scroll mode the wheel's.
+    fn clicked_button(&self) -> u32 {
+        if self.scroll.enabled {
+            self.scroll.direction.button()
+        } else {
+            parse_button(&self.button_name).unwrap_or(1)
+        }
+    }
+
+    fn timing(&self) -> ClickTiming {
+        if self.scroll.enabled {
+            let period = self.scroll.period();
+            return ClickTiming { period, on_time: ClickTiming::MIN_PRESS.min(period) };
+        }
+        let cps = if self.cps > 0.0 { self.cps } else { 0.1 };
+        let duty = (self.duty / 100.0).clamp(0.0, 1.0);
+        let period = 1.0 / cps;
+        let on_time = (period * duty).max(ClickTiming::MIN_PRESS).min(period);
+        ClickTiming { period, on_time }
+    }
+}
+
 fn parse_button(name: &str) -> Result<u32> {
     let b = name.to_lowercase();
     let v = match b.as_str() {
         "left" => 1,
         "middle" => 2,
         "right" => 3,
-        _ => b.parse::<u32>().context("button must be left|middle|right|1..9")?,
+        _ => b.parse::<u32>().context("button must be left|middle|right|1..3|8|9")?,
     };
+    if (4..=7).contains(&v) {
+        bail!("buttons 4..7 are the scroll wheel; use scroll mode (--scroll up|down|left|right)");
+    }
     if !(1..=9).contains(&v) {
-        bail!("button must be in 1..=9");
+        bail!("button must be in 1..=3 or 8..=9");
     }
     Ok(v)
 }
 
+/// What click_thread holds down each cycle: a mouse button or a key chord.
+#[derive(Clone, PartialEq)]
+enum Press {
+    Button(u32),
+    /// Keysym names, modifiers first.
+    Keys(Vec<String>),
+}
+
+impl Press {
+    fn down(&self, out: &mut dyn ClickBackend) -> Result<()> {
+        match self {
+            Press::Button(b) => out.press(*b),
+            Press::Keys(keys) => keys.iter().try_for_each(|k| out.key(k, true)),
+        }
+    }
+
+    /// Keys come up in reverse, so the modifiers outlast the key.
+    fn up(&self, out: &mut dyn ClickBackend) -> Result<()> {
+        match self {
+            Press::Button(b) => out.release(*b),
+            Press::Keys(keys) => keys.iter().rev().try_for_each(|k| out.key(k, false)),
+        }
+    }
+}
+
+/// One scroll mode event: a burst of ticks, or one smooth step.
+fn scroll_once(out: &mut dyn ClickBackend, sc: &Scroll) -> Result<()> {
+    let (dx, dy) = sc.direction.delta();
+    if sc.smooth {
+        let step = 120 / Scroll::SMOOTH_STEPS as i32;
+        out.scroll_hi_res(dx * step, dy * step)
+    } else {
+        let n = sc.burst.max(1) as i32;
+        out.scroll(dx * n, dy * n)
+    }
+}
+
+/// Keysyms to press for a `press_key` chord such as "Ctrl+c".
+fn parse_press_key(text: &str) -> Result<Vec<String>> {
+    let accel = Accel::parse(text)?;
+    if accel.button().is_some() {
+        bail!("'{text}' is a mouse button, not a key");
+    }
+    Ok(accel.keysyms())
+}
+
 fn keysym_to_keycode(display: *mut Display, name: &str) -> Result<u32> {
     let c = std::ffi::CString::new(name)?;
     unsafe {
@@ -64,6 +256,18 @@ fn keysym_to_keycode(display: *mut Display, name: &str) -> Result<u32> {
     }
 }
 
+/// Resolve an accelerator string ("F6", "Ctrl+Alt+c", "Button8") to a
+/// keycode or button + mask.
+fn resolve_hotkey(display: *mut Display, text: &str) -> Result<KeyCombo> {
+    let accel = Accel::parse(text)?;
+    let trigger = match accel.button() {
+        Some(b) if Accel::usable_button(b) => Trigger::Button(b),
+        Some(b) => bail!("can't use mouse button {b} as a hotkey"),
+        None => Trigger::Key(keysym_to_keycode(display, &accel.key)?),
+    };
+    Ok(KeyCombo { trigger, mods: accel.mods })
+}
+
 // Mod combinations to handle NumLock/CapsLock variations
 const MOD_VARIANTS: [u32; 8] = [
     0,
@@ -76,11 +280,373 @@ const MOD_VARIANTS: [u32; 8] = [
     LockMask | Mod2Mask | Mod5Mask,
 ];
 
+/// Grab `combo` in every MOD_VARIANTS lock state; returns the variants
+/// another client already holds (the grab fails with BadAccess for those).
+unsafe fn grab_combo(dpy: *mut Display, root: Window, combo: KeyCombo) -> Vec<u32> {
+    let mut serials = Vec::new();
+    let errors = xerror::trap(dpy, || {
+        for m in MOD_VARIANTS {
+            serials.push((XNextRequest(dpy), m));
+            let mods = combo.mods | m;
+            match combo.trigger {
+                Trigger::Key(kc) => {
+                    XGrabKey(dpy, kc as i32, mods, root, True, GrabModeAsync, GrabModeAsync);
+                }
+                Trigger::Button(b) => {
+                    let mask = (ButtonPressMask | ButtonReleaseMask) as u32;
+                    XGrabButton(dpy, b, mods, root, False, mask, GrabModeAsync, GrabModeAsync, 0, 0);
+                }
+            }
+        }
+    });
+    serials
+        .into_iter()
+        .filter(|(serial, _)| errors.iter().any(|e| e.is_bad_access() && e.serial == *serial))
+        .map(|(_, m)| m)
+        .collect()
+}
+
+unsafe fn ungrab_combo(dpy: *mut Display, root: Window, combo: KeyCombo) {
+    for m in MOD_VARIANTS {
+        match combo.trigger {
+            Trigger::Key(kc) => XUngrabKey(dpy, kc as i32, combo.mods | m, root),
+            Trigger::Button(b) => XUngrabButton(dpy, b, combo.mods | m, root),
+        };
+    }
+}
+
+/// "NumLock+CapsLock" for a MOD_VARIANTS entry, "no locks" for 0.
+fn variant_name(m: u32) -> String {
+    let names: Vec<&str> = [(Mod2Mask, "NumLock"), (LockMask, "CapsLock"), (Mod5Mask, "Mod5")]
+        .into_iter()
+        .filter(|(bit, _)| m & bit != 0)
+        .map(|(_, name)| name)
+        .collect();
+    if names.is_empty() {
+        "no locks".to_string()
+    } else {
+        names.join("+")
+    }
+}
+
 // ---------- Hotkey thread ----------
+/// What a bound key does.
+#[derive(Clone, PartialEq, Debug)]
+enum HotkeyAction {
+    /// The main hotkey, acting per Settings::activation.
+    Toggle,
+    Start,
+    Stop,
+    /// Stop immediately, release the button and quit.
+    Kill,
+    /// Select this profile, then act per its activation mode.
+    Profile(String),
+}
+
+/// One grabbed key: the combo, what it does and the text it came from.
+#[derive(PartialEq)]
+struct Binding {
+    combo: KeyCombo,
+    action: HotkeyAction,
+    text: String,
+}
+
+/// Key combos hotkey_thread listens on.
+#[derive(Default)]
+struct Bindings {
+    keys: Vec<Binding>,
+    /// Watched through XI2 raw events instead of grabbed.
+    raw: bool,
+    /// Modifiers of the key chord click_thread presses. They are down for
+    /// most of a run, so every combo also fires with them held.
+    injected_mods: u32,
+    /// Triggers currently down and the action their press fired, so the
+    /// release goes to the same binding.
+    fired: Vec<(Trigger, HotkeyAction)>,
+}
+
+/// Same combos and mode; what is held down doesn't matter.
+impl PartialEq for Bindings {
+    fn eq(&self, other: &Self) -> bool {
+        self.keys == other.keys && self.raw == other.raw && self.injected_mods == other.injected_mods
+    }
+}
+
+impl Bindings {
+    /// Resolve accelerators to key combos. Hotkeys that don't resolve,
+    /// that collide with an earlier binding or that are the button or key
+    /// being pressed are left out and appended to `problems`. Kill goes
+    /// first so nothing can shadow it.
+    fn resolve(dpy: *mut Display, s: &Settings, profiles: &Profiles, problems: &mut Vec<WorkerError>) -> Self {
+        // A trigger that click_thread injects would fire itself
+        let usable = |text: &str, s: &Settings| -> Result<KeyCombo> {
+            let combo = resolve_hotkey(dpy, text)?;
+            if !s.scroll.enabled && !s.press_key.is_empty() {
+                if resolve_hotkey(dpy, &s.press_key).is_ok_and(|k| k.trigger == combo.trigger) {
+                    bail!("it is the key being pressed");
+                }
+            } else if combo.trigger == Trigger::Button(s.clicked_button()) {
+                bail!("it is the mouse button being clicked");
+            }
+            Ok(combo)
+        };
+
+        let all = [
+            (&s.kill_hotkey, HotkeyAction::Kill, s),
+            (&s.hotkey, HotkeyAction::Toggle, s),
+            (&s.start_hotkey, HotkeyAction::Start, s),
+            (&s.stop_hotkey, HotkeyAction::Stop, s),
+        ];
+        let all = all.into_iter().chain(profiles.list.iter().map(|p| {
+            // The active profile's settings are the live ones
+            let ps = if p.name == profiles.active { s } else { &p.settings };
+            (&p.hotkey, HotkeyAction::Profile(p.name.clone()), ps)
+        }));
+
+        let mut out = Self::default();
+        if !s.scroll.enabled && !s.press_key.is_empty() {
+            out.injected_mods = Accel::parse(&s.press_key).map_or(0, |a| a.mods);
+        }
+        for (text, action, settings) in all {
+            let text = text.trim();
+            if text.is_empty() {
+                continue;
+            }
+            let reason = match usable(text, settings) {
+                Ok(combo) if !out.keys.iter().any(|b| b.combo == combo) => {
+                    let text = text.to_string();
+                    out.keys.push(Binding { combo, action, text });
+                    continue;
+                }
+                Ok(_) => "already used by another binding".to_string(),
+                Err(e) => format!("{e:#}"),
+            };
+            problems.push(WorkerError::BadHotkey { key: text.to_string(), reason });
+        }
+        out
+    }
+
+    /// Grab every key, returning the ones another client already owns
+    /// together with a few free keys to use instead.
+    unsafe fn grab(&self, dpy: *mut Display, root: Window) -> Vec<WorkerError> {
+        let mut out = Vec::new();
+        if self.raw {
+            return out;
+        }
+        for b in &self.keys {
+            let mut failed = Vec::new();
+            for combo in self.combos(b) {
+                for m in grab_combo(dpy, root, combo) {
+                    if !failed.contains(&m) {
+                        failed.push(m);
+                    }
+                }
+            }
+            if failed.is_empty() {
+                continue;
+            }
+            out.push(WorkerError::GrabConflict {
+                key: b.text.clone(),
+                variants: failed.into_iter().map(variant_name).collect(),
+                suggestions: self.free_keys(dpy, root, &b.text, 3),
+            });
+        }
+        out
+    }
+
+    /// Up to `n` accelerators nobody has grabbed, preferring `key` with an
+    /// extra modifier, then the function keys.
+    unsafe fn free_keys(&self, dpy: *mut Display, root: Window, key: &str, n: usize) -> Vec<String> {
+        let Ok(base) = Accel::parse(key) else { return Vec::new() };
+        let extra = [ControlMask, ShiftMask, Mod1Mask, ControlMask | ShiftMask];
+        let same_key = extra
+            .into_iter()
+            .filter(|m| base.mods & m == 0)
+            .map(|m| Accel { mods: base.mods | m, key: base.key.clone() });
+        let fkeys = [0, ControlMask, ShiftMask].into_iter().flat_map(|mods| {
+            (1..=12).map(move |i| Accel { mods, key: format!("F{i}") })
+        });
+
+        let mut found = Vec::new();
+        for accel in same_key.chain(fkeys) {
+            if found.len() == n {
+                break;
+            }
+            let Ok(combo) = resolve_hotkey(dpy, &accel.to_string()) else { continue };
+            // Our own keys would "succeed" and then lose their grab
+            if self.keys.iter().any(|b| b.combo == combo) {
+                continue;
+            }
+            let free = grab_combo(dpy, root, combo).is_empty();
+            ungrab_combo(dpy, root, combo);
+            if free {
+                found.push(accel.to_string());
+            }
+        }
+        XFlush(dpy);
+        found
+    }
+
+    unsafe fn ungrab(&self, dpy: *mut Display, root: Window) {
+        if self.raw {
+            return;
+        }
+        for b in &self.keys {
+            for combo in self.combos(b) {
+                ungrab_combo(dpy, root, combo);
+            }
+        }
+    }
+
+    /// What to grab for `b`: its combo, and the combo with injected_mods
+    /// added unless that is another binding's own.
+    fn combos(&self, b: &Binding) -> Vec<KeyCombo> {
+        let mut out = vec![b.combo];
+        let held = KeyCombo { mods: b.combo.mods | self.injected_mods, ..b.combo };
+        if held != b.combo && !self.keys.iter().any(|k| k.combo == held) {
+            out.push(held);
+        }
+        out
+    }
+
+    fn describe(&self) -> String {
+        let list: Vec<String> = self
+            .keys
+            .iter()
+            .map(|b| {
+                let what = match &b.action {
+                    HotkeyAction::Toggle => "hotkey".to_string(),
+                    HotkeyAction::Start => "start".to_string(),
+                    HotkeyAction::Stop => "stop".to_string(),
+                    HotkeyAction::Kill => "kill".to_string(),
+                    HotkeyAction::Profile(name) => format!("profile '{name}'"),
+                };
+                format!("{} ({what})", b.text)
+            })
+            .collect();
+        let how = if self.raw { "watching" } else { "bound" };
+        format!("{how} {}", list.join(", "))
+    }
+
+    /// Which binding an event is for. A release goes to whatever its
+    /// press fired, since modifiers may already be up by then.
+    fn lookup(&mut self, trigger: Trigger, state: u32, pressed: bool) -> Option<HotkeyAction> {
+        if !pressed {
+            let i = self.fired.iter().position(|(t, _)| *t == trigger)?;
+            return Some(self.fired.remove(i).1);
+        }
+        // An exact match wins over one that ignores our injected modifiers
+        let loose = state & !self.injected_mods;
+        let action = self
+            .keys
+            .iter()
+            .find(|b| b.combo.matches(trigger, state))
+            .or_else(|| self.keys.iter().find(|b| b.combo.matches(trigger, loose)))
+            .map(|b| b.action.clone())?;
+        self.fired.retain(|(t, _)| *t != trigger);
+        self.fired.push((trigger, action.clone()));
+        Some(action)
+    }
+}
+
+/// Apply a hotkey press/release to `running` according to the mode.
+fn activate(running: &AtomicBool, report: &Reporter, mode: Activation, pressed: bool, profile: Option<&str>) {
+    let was = running.load(Ordering::SeqCst);
+    let now = match (mode, pressed) {
+        // Profile keys always start; plain toggle flips
+        (Activation::Toggle, true) => profile.is_some() || !was,
+        (Activation::Hold, p) => p,
+        // A burst runs to its click count; pressing mid-burst is ignored
+        (Activation::Burst, true) => true,
+        (_, false) => was,
+    };
+    set_running(running, report, now, profile);
+}
+
+fn set_running(running: &AtomicBool, report: &Reporter, on: bool, profile: Option<&str>) {
+    if running.swap(on, Ordering::SeqCst) != on {
+        let what = if on { "START" } else { "STOP" };
+        match profile {
+            Some(name) => report.status(format!("{what} profile '{name}'")),
+            None => report.status(what),
+        }
+    }
+}
+
+/// Current keyboard modifiers (and pointer buttons) as an event state mask.
+unsafe fn modifier_state(dpy: *mut Display, root: Window) -> u32 {
+    let (mut root_ret, mut child) = (0, 0);
+    let (mut rx, mut ry, mut wx, mut wy) = (0, 0, 0, 0);
+    let mut mask = 0;
+    XQueryPointer(dpy, root, &mut root_ret, &mut child, &mut rx, &mut ry, &mut wx, &mut wy, &mut mask);
+    mask
+}
+
+/// Without detectable auto-repeat a held key produces KeyRelease/KeyPress
+/// pairs with the same timestamp; swallow both halves of such a pair.
+unsafe fn is_auto_repeat(dpy: *mut Display, release: &XKeyEvent) -> bool {
+    const QUEUED_AFTER_READING: i32 = 1; // Xlib.h, not exported by x11
+    if XEventsQueued(dpy, QUEUED_AFTER_READING) == 0 {
+        return false;
+    }
+    let mut next: XEvent = std::mem::zeroed();
+    XPeekEvent(dpy, &mut next);
+    if next.get_type() == KeyPress && next.key.keycode == release.keycode && next.key.time == release.time {
+        XNextEvent(dpy, &mut next);
+        return true;
+    }
+    false
+}
+
+/// Ask for Xkb keyboard-change and keymap notifications; returns the Xkb
+/// event type, or None without the extension.
+unsafe fn xkb_mapping_events(dpy: *mut Display) -> Option<i32> {
+    const XKB_USE_CORE_KBD: u32 = 0x0100; // XKB.h, not exported by x11
+    let (mut opcode, mut event_base, mut error_base) = (0, 0, 0);
+    let (mut major, mut minor) = (1, 0);
+    if XkbQueryExtension(dpy, &mut opcode, &mut event_base, &mut error_base, &mut major, &mut minor) == 0 {
+        return None;
+    }
+    let mask = XkbNewKeyboardNotifyMask | XkbMapNotifyMask;
+    XkbSelectEvents(dpy, XKB_USE_CORE_KBD, mask, mask);
+    Some(event_base)
+}
+
+/// Everything a rebind reads from the shared state: the four hotkeys, the
+/// clicked button, the backend and each profile's name, key and button.
+/// Cheap to compare.
+#[derive(Clone, PartialEq, Default)]
+struct HotkeyInputs(Vec<String>);
+
+fn hotkey_inputs(profiles: &Mutex<Profiles>, settings: &Mutex<Settings>) -> HotkeyInputs {
+    let p = profiles.lock().unwrap();
+    let s = settings.lock().unwrap();
+    let own = [&s.hotkey, &s.start_hotkey, &s.stop_hotkey, &s.kill_hotkey, &s.press_key];
+    let mut v: Vec<String> = own.into_iter().cloned().collect();
+    v.push(s.clicked_button().to_string());
+    v.push(format!("{:?}", s.hotkey_backend));
+    v.extend(
+        p.list
+            .iter()
+            .map(|p| format!("{}\0{}\0{}\0{}", p.name, p.hotkey, p.settings.clicked_button(), p.settings.press_key)),
+    );
+    HotkeyInputs(v)
+}
+
+/// Snapshot settings and the profile list (Profiles before Settings).
+fn hotkey_config(profiles: &Mutex<Profiles>, settings: &Mutex<Settings>) -> (Settings, Profiles) {
+    let p = profiles.lock().unwrap().clone();
+    let s = settings.lock().unwrap().clone();
+    (s, p)
+}
+
 fn hotkey_thread(
     running: Arc<AtomicBool>,
     should_exit: Arc<AtomicBool>,
     settings: Arc<Mutex<Settings>>,
+    profiles: Arc<Mutex<Profiles>>,
+    wakeup: Arc<Wakeup>,
+    report: Reporter,
 ) -> Result<()> {
     unsafe { XInitThreads() };
     unsafe {
@@ -90,120 +656,587 @@ fn hotkey_thread(
         }
         let screen = XDefaultScreen(dpy);
         let root = XRootWindow(dpy, screen);
-        XSelectInput(dpy, root, KeyPressMask);
+        XSelectInput(dpy, root, KeyPressMask | KeyReleaseMask);
 
-        // Initial grab + last_keycode
-        let s0 = settings.lock().unwrap().clone();
-        let mut last_keycode = keysym_to_keycode(dpy, &s0.hotkey)?;
-        for m in MOD_VARIANTS {
-            XGrabKey(dpy, last_keycode as i32, m, root, True, GrabModeAsync, GrabModeAsync);
-        }
-        XFlush(dpy);
+        // Hold mode needs real releases: with detectable auto-repeat the
+        // server stops sending a KeyRelease before every repeated KeyPress.
+        let mut supported = 0;
+        XkbSetDetectableAutoRepeat(dpy, True, &mut supported);
+        let detectable_repeat = supported != 0;
+
+        // Layout switches (setxkbmap) arrive as Xkb events, xmodmap as core
+        // MappingNotify, which every client gets without selecting it.
+        let xkb_event = xkb_mapping_events(dpy);
+
+        // A mouse-button hotkey's press activates a pointer grab that would
+        // also swallow the clicks we inject, so we drop it right away and
+        // watch for the release as a raw XI2 event instead. The raw backend
+        // watches presses the same way; our own injected input is ignored.
+        let xi_opcode = xi::opcode(dpy);
+        let mut sources = xi::Sources::default();
+        // Triggers currently down, to drop raw auto-repeat presses
+        let mut held: Vec<Trigger> = Vec::new();
 
-        // Event loop
+        // Event loop; the first pass does the initial grab
         let mut event: XEvent = std::mem::zeroed();
+        let mut bound = Bindings::default();
+        let mut reported: Vec<String> = Vec::new();
+        let mut inputs: Option<HotkeyInputs> = None;
+        let mut remapped = false;
 
         while !should_exit.load(Ordering::SeqCst) {
-            // Re-grab if hotkey changed
-            if let Some(nk) = {
-                let s = settings.lock().unwrap().clone();
-                keysym_to_keycode(dpy, &s.hotkey).ok()
-            } {
-                if nk != last_keycode {
-                    for m in MOD_VARIANTS {
-                        XUngrabKey(dpy, last_keycode as i32, m, root);
+            // Re-grab if any hotkey changed
+            let current = hotkey_inputs(&profiles, &settings);
+            if remapped || inputs.as_ref() != Some(&current) {
+                inputs = Some(current);
+                let (s, p) = hotkey_config(&profiles, &settings);
+                let mut problems = Vec::new();
+                let mut nb = Bindings::resolve(dpy, &s, &p, &mut problems);
+                if s.hotkey_backend == HotkeyBackend::Raw {
+                    nb.raw = xi_opcode.is_some();
+                    if !nb.raw {
+                        problems.push(WorkerError::NoXInput2);
+                    }
+                }
+                if remapped || nb != bound {
+                    bound.ungrab(dpy, root);
+                    // Keys held across the rebind still release as pressed
+                    nb.fired = std::mem::take(&mut bound.fired);
+                    // Grab conflicts are only known right after grabbing
+                    let conflicts = nb.grab(dpy, root);
+                    bound = nb;
+                    held.clear();
+                    if xi_opcode.is_some() {
+                        let all = [XI_RawKeyPress, XI_RawKeyRelease, XI_RawButtonPress, XI_RawButtonRelease];
+                        xi::select_raw(dpy, root, if bound.raw { &all } else { &all[3..] });
+                    }
+                    if remapped {
+                        report.status(format!("keyboard mapping changed, re-{}", bound.describe()));
+                    } else {
+                        report.status(bound.describe());
                     }
-                    for m in MOD_VARIANTS {
-                        XGrabKey(dpy, nk as i32, m, root, True, GrabModeAsync, GrabModeAsync);
+                    for c in conflicts {
+                        report.error(c);
                     }
-                    last_keycode = nk;
-                    XFlush(dpy);
-                    eprintln!("[hotkey] rebound");
                 }
+
+                // Report each problem once, not again when another key changes
+                let texts: Vec<String> = problems.iter().map(|e| e.to_string()).collect();
+                if texts != reported {
+                    for e in problems.into_iter().filter(|e| !reported.contains(&e.to_string())) {
+                        report.error(e);
+                    }
+                    reported = texts;
+                }
+                remapped = false;
             }
 
-            // Handle events
-            if XPending(dpy) > 0 {
+            // Sleep until the X server or another thread has news for us
+            if XPending(dpy) == 0 {
+                wakeup.wait_with(XConnectionNumber(dpy));
+            }
+
+            // Handle every queued event
+            while XPending(dpy) > 0 {
                 XNextEvent(dpy, &mut event);
-                if event.get_type() == KeyPress {
+                let ty = event.get_type();
+                if ty == MappingNotify {
+                    XRefreshKeyboardMapping(&mut event.mapping);
+                    remapped = true;
+                    continue;
+                }
+                if Some(ty) == xkb_event {
+                    let xkb = &*(&event as *const XEvent as *const XkbAnyEvent);
+                    if xkb.xkb_type == XkbNewKeyboardNotify || xkb.xkb_type == XkbMapNotify {
+                        XkbRefreshKeyboardMapping(&mut event as *mut XEvent as *mut XkbMapNotifyEvent);
+                        remapped = true;
+                    }
+                    continue;
+                }
+                let (trigger, state, pressed) = if ty == KeyPress || ty == KeyRelease {
                     let xkey: XKeyEvent = event.key;
-                    if xkey.keycode as u32 == last_keycode {
-                        let new_state = !running.load(Ordering::SeqCst);
-                        running.store(new_state, Ordering::SeqCst);
-                        eprintln!("[hotkey] {}", if new_state { "START" } else { "STOP" });
+                    if bound.raw || ty == KeyRelease && !detectable_repeat && is_auto_repeat(dpy, &xkey) {
+                        continue;
+                    }
+                    (Trigger::Key(xkey.keycode), xkey.state, ty == KeyPress)
+                } else if ty == ButtonPress || ty == ButtonRelease {
+                    let b = event.button;
+                    if bound.raw {
+                        continue;
                     }
+                    if ty == ButtonPress && xi_opcode.is_some() {
+                        XUngrabPointer(dpy, b.time);
+                    }
+                    (Trigger::Button(b.button), b.state, ty == ButtonPress)
+                } else {
+                    let Some(r) = xi_opcode.and_then(|op| xi::raw_event(dpy, &mut event, op)) else {
+                        continue;
+                    };
+                    let is_key = r.evtype == XI_RawKeyPress || r.evtype == XI_RawKeyRelease;
+                    let pressed = r.evtype == XI_RawKeyPress || r.evtype == XI_RawButtonPress;
+                    if pressed && !bound.raw || sources.is_injected(dpy, r.sourceid) {
+                        continue;
+                    }
+                    let trigger = if is_key { Trigger::Key(r.detail as u32) } else { Trigger::Button(r.detail as u32) };
+                    if bound.raw && pressed {
+                        if held.contains(&trigger) {
+                            continue;
+                        }
+                        held.push(trigger);
+                    } else {
+                        held.retain(|t| *t != trigger);
+                    }
+                    // Raw events carry no modifier state; ask only when it matters
+                    let state = if pressed && bound.keys.iter().any(|b| b.combo.trigger == trigger) {
+                        modifier_state(dpy, root)
+                    } else {
+                        0
+                    };
+                    (trigger, state, pressed)
+                };
+
+                match bound.lookup(trigger, state, pressed) {
+                    None => {}
+                    Some(HotkeyAction::Toggle) => {
+                        let mode = settings.lock().unwrap().activation;
+                        activate(&running, &report, mode, pressed, None);
+                    }
+                    Some(HotkeyAction::Start) if pressed => set_running(&running, &report, true, None),
+                    Some(HotkeyAction::Stop) if pressed => set_running(&running, &report, false, None),
+                    Some(HotkeyAction::Kill) if pressed => {
+                        // click_thread releases the button on its way out
+                        running.store(false, Ordering::SeqCst);
+                        should_exit.store(true, Ordering::SeqCst);
+                        report.status("KILL");
+                    }
+                    Some(HotkeyAction::Profile(name)) => {
+                        // Profile keys select on press, then act in that
+                        // profile's activation mode.
+                        let mut p = profiles.lock().unwrap();
+                        let mut s = settings.lock().unwrap();
+                        if pressed && p.active != name && p.switch(&name, &mut s).is_ok() {
+                            running.store(false, Ordering::SeqCst);
+                        }
+                        let mode = s.activation;
+                        activate(&running, &report, mode, pressed, Some(&name));
+                    }
+                    Some(_) => {} // releases of start/stop/kill
                 }
-            } else {
-                std::thread::sleep(Duration::from_millis(20));
             }
         }
 
         // Cleanup
-        for m in MOD_VARIANTS {
-            XUngrabKey(dpy, last_keycode as i32, m, root);
-        }
+        bound.ungrab(dpy, root);
         XFlush(dpy);
         XCloseDisplay(dpy);
     }
     Ok(())
 }
 
+// ---------- Scheduler ----------
+// Every press is planned at `origin + cycle * period (+ jitter)` and its
+// release at `press + on_time`, so the time spent flushing, locking and
+// parsing never accumulates and the long-run rate is exactly Settings::cps.
+struct Scheduler {
+    origin: Instant,
+    cycle: u64,
+    timing: ClickTiming,
+    // Humanization: accumulated deviation from the grid in seconds, and
+    // the multipliers drawn for the current cycle.
+    offset: f64,
+    period_factor: f64,
+    press_factor: f64,
+}
+
+impl Scheduler {
+    /// How far behind the grid we may fall before dropping missed clicks
+    /// instead of firing them back to back.
+    const MAX_CATCH_UP_CYCLES: f64 = 3.0;
+
+    fn new(origin: Instant, timing: ClickTiming) -> Self {
+        Self {
+            origin,
+            cycle: 0,
+            timing,
+            offset: 0.0,
+            period_factor: 1.0,
+            press_factor: 1.0,
+        }
+    }
+
+    fn at(&self, cycle: u64) -> Instant {
+        let t = cycle as f64 * self.timing.period + self.offset;
+        self.origin + Duration::from_secs_f64(t.max(0.0))
+    }
+
+    /// Length of the current cycle, i.e. the gap to the next press.
+    fn cycle_period(&self) -> f64 {
+        self.timing.period * self.period_factor
+    }
+
+    fn cycle_on_time(&self) -> f64 {
+        let p = self.cycle_period();
+        (self.timing.on_time * self.press_factor)
+            .max(ClickTiming::MIN_PRESS)
+            .min(p)
+    }
+
+    fn press_deadline(&self) -> Instant {
+        self.at(self.cycle)
+    }
+
+    fn release_deadline(&self) -> Instant {
+        self.press_deadline() + Duration::from_secs_f64(self.cycle_on_time())
+    }
+
+    /// Apply new timing without a phase jump: the pending press stays where
+    /// it is and the new period applies from there on.
+    fn retime(&mut self, timing: ClickTiming) {
+        if timing != self.timing {
+            self.origin = self.press_deadline();
+            self.cycle = 0;
+            self.offset = 0.0;
+            self.timing = timing;
+        }
+    }
+
+    /// Set the humanization multipliers for the current cycle.
+    fn vary(&mut self, period_factor: f64, press_factor: f64) {
+        self.period_factor = period_factor;
+        self.press_factor = press_factor;
+    }
+
+    /// Move to the next cycle. Late presses are caught up; if we are more
+    /// than MAX_CATCH_UP_CYCLES behind (e.g. after a stall), the missed
+    /// cycles are skipped and we rejoin the original grid.
+    fn advance(&mut self, now: Instant) {
+        self.offset += self.cycle_period() - self.timing.period;
+        self.cycle += 1;
+        self.vary(1.0, 1.0);
+        let next = self.press_deadline();
+        if now > next {
+            let behind = now.duration_since(next).as_secs_f64() / self.timing.period;
+            if behind > Self::MAX_CATCH_UP_CYCLES {
+                let elapsed = now.duration_since(self.origin).as_secs_f64() - self.offset;
+                self.cycle = (elapsed / self.timing.period).ceil().max(0.0) as u64;
+            }
+        }
+    }
+}
+
+/// Sleep until `deadline`, waking periodically so a stop or exit request is
+/// noticed during long gaps. Returns false if `keep_going` turned false.
+fn sleep_until(sleeper: &SpinSleeper, deadline: Instant, keep_going: impl Fn() -> bool) -> bool {
+    const SLICE: Duration = Duration::from_millis(20);
+    loop {
+        if !keep_going() {
+            return false;
+        }
+        let left = deadline.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            return true;
+        }
+        sleeper.sleep(left.min(SLICE));
+    }
+}
+
 // ---------- Click thread ----------
 fn click_thread(
     running: Arc<AtomicBool>,
     should_exit: Arc<AtomicBool>,
     settings: Arc<Mutex<Settings>>,
+    stats: Arc<Mutex<ClickStats>>,
+    report: Reporter,
 ) -> Result<()> {
     unsafe { XInitThreads() };
-    unsafe {
-        let dpy = XOpenDisplay(ptr::null());
-        if dpy.is_null() {
-            bail!("Click thread: failed to open X display (X11 only)");
-        }
+    // Opened when a run starts, so a backend that can't open now (uinput
+    // not writable yet) only fails that run
+    let mut out: Option<Box<dyn ClickBackend>> = None;
+    let mut kind = BackendKind::default();
+    let mut window = TargetWindow::default();
 
-        // High-resolution sleep without explicit SpinStrategy variant
-        let sleeper = SpinSleeper::new(1_000_000);
+    // High-resolution sleep without explicit SpinStrategy variant
+    let sleeper = SpinSleeper::new(1_000_000);
 
-        // Ensure button is released on exit
-        let mut last_button: u32 = 1;
+    // Ensure button (or key) is released on exit
+    let mut last = Press::Button(1);
+    let mut sched: Option<Scheduler> = None;
+    let mut humanizer = Humanizer::new(None);
+    let mut limit: Option<(Limits, RunLimit)> = None;
+    let mut completed: u64 = 0;
+    let mut cycler = TargetCycler::default();
+    // Last button name reported as invalid, so it's reported once
+    let mut bad_button: Option<String> = None;
+    let mut bad_key: Option<String> = None;
+    // Window lock: opened on first use; the window active at run start
+    let mut focus: Option<FocusWatch> = None;
+    let mut locked: Option<Window> = None;
+    let mut paused = false;
+    // Panic corner pointer queries, opened on first use
+    let mut corner_watch: Option<CornerWatch> = None;
+    let active = || running.load(Ordering::SeqCst) && !should_exit.load(Ordering::SeqCst);
+    // A backend that fails stops the run; the next start tries again
+    let fail = |e: anyhow::Error| {
+        running.store(false, Ordering::SeqCst);
+        report.error(WorkerError::Backend(format!("{e:#}")));
+    };
 
-        while !should_exit.load(Ordering::SeqCst) {
-            if running.load(Ordering::SeqCst) {
-                // Snapshot settings
-                let s = settings.lock().unwrap().clone();
+    while !should_exit.load(Ordering::SeqCst) {
+        if running.load(Ordering::SeqCst) {
+            // Snapshot settings
+            let s = settings.lock().unwrap().clone();
+            let timing = s.timing();
+            let button = match parse_button(&s.button_name) {
+                Ok(b) => b,
+                Err(e) => {
+                    if bad_button.as_ref() != Some(&s.button_name) {
+                        report.error(WorkerError::BadButton(format!("{e:#}")));
+                        bad_button = Some(s.button_name.clone());
+                    }
+                    1
+                }
+            };
+            let press = if s.press_key.is_empty() {
+                Press::Button(button)
+            } else {
+                match parse_press_key(&s.press_key) {
+                    Ok(keys) => Press::Keys(keys),
+                    Err(e) => {
+                        if bad_key.as_ref() != Some(&s.press_key) {
+                            report.error(WorkerError::BadKey(format!("{e:#}")));
+                            bad_key = Some(s.press_key.clone());
+                        }
+                        Press::Button(button)
+                    }
+                }
+            };
 
-                let cps = if s.cps > 0.0 { s.cps } else { 0.1 };
-                let duty = (s.duty / 100.0).clamp(0.0, 1.0);
-                let button = parse_button(&s.button_name).unwrap_or(1);
-                last_button = button;
+            // Open the backend, or switch backends (or target windows)
+            // between clicks
+            let retarget = s.click_backend == BackendKind::Window && s.window != window;
+            if out.is_none() || s.click_backend != kind || retarget {
+                let switching = out.take().map(|mut old| {
+                    let _ = last.up(&mut *old);
+                    old.flush();
+                });
+                match backend::open(s.click_backend, &s.window) {
+                    Ok(b) => {
+                        out = Some(b);
+                        kind = s.click_backend;
+                        window = s.window.clone();
+                        if switching.is_some() {
+                            report.status(format!("clicking via {}", kind.name()));
+                        }
+                    }
+                    Err(e) => {
+                        fail(e);
+                        continue;
+                    }
+                }
+            }
+            let Some(out) = out.as_deref_mut() else { continue };
 
-                let period = 1.0 / cps;
-                let min_press = 0.001_f64; // 1 ms
-                let on_time = (period * duty).max(min_press).min(period);
-                let off_time = (period - on_time).max(0.0);
+            // A fresh run starts its grid and its statistics now
+            let sched = sched.get_or_insert_with(|| {
+                stats.lock().unwrap().reset();
+                humanizer = Humanizer::new(s.humanize.seed);
+                limit = None;
+                completed = 0;
+                cycler = TargetCycler::default();
+                Scheduler::new(Instant::now(), timing)
+            });
+            sched.retime(timing);
 
-                // Press
-                XTestFakeButtonEvent(dpy, button, True, CurrentTime);
-                XFlush(dpy);
-                sleeper.sleep(Duration::from_secs_f64(on_time));
+            // Hold off while another window has focus
+            if s.focus_lock.enabled {
+                let watch = match &mut focus {
+                    Some(w) => w,
+                    None => match FocusWatch::new() {
+                        Ok(w) => focus.insert(w),
+                        Err(e) => {
+                            running.store(false, Ordering::SeqCst);
+                            report.error(WorkerError::FocusLock(format!("{e:#}")));
+                            continue;
+                        }
+                    },
+                };
+                let now_active = watch.active();
+                let locked = *locked.get_or_insert(now_active);
+                if now_active != locked {
+                    match s.focus_lock.on_leave {
+                        OnLeave::Pause => {
+                            if !paused {
+                                paused = true;
+                                report.status("paused: focus left the locked window");
+                            }
+                            watch.wait(Duration::from_millis(50));
+                        }
+                        OnLeave::Stop => {
+                            running.store(false, Ordering::SeqCst);
+                            report.status("stopped: focus left the locked window");
+                        }
+                    }
+                    continue;
+                }
+                if paused {
+                    paused = false;
+                    report.status("resumed: locked window has focus again");
+                    // Don't try to make up for the clicks skipped meanwhile
+                    *sched = Scheduler::new(Instant::now(), timing);
+                }
+            } else {
+                // Turning the lock back on picks up the window active then
+                locked = None;
+                if paused {
+                    paused = false;
+                    *sched = Scheduler::new(Instant::now(), timing);
+                }
+            }
 
-                // Release
-                XTestFakeButtonEvent(dpy, button, False, CurrentTime);
-                XFlush(dpy);
+            let (pf, qf) = humanizer.draw(&s.humanize);
+            sched.vary(pf, qf);
+
+            // (Re)resolve stop conditions against the run start
+            let limits = s.run_limits();
+            if limit.as_ref().map(|(l, _)| l) != Some(&limits) {
+                let started = limit.as_ref().map_or(Instant::now(), |(_, r)| r.started);
+                let run = RunLimit::new(&limits, started).unwrap_or_else(|e| {
+                    report.error(WorkerError::BadStopTime(format!("{e:#}")));
+                    let l = Limits { stop_at: None, ..limits.clone() };
+                    RunLimit::new(&l, started).unwrap()
+                });
+                let shown = (!limits.is_empty()).then(|| run.clone());
+                stats.lock().unwrap().set_limit(shown);
+                limit = Some((limits, run));
+            }
+            let run = &limit.as_ref().unwrap().1;
+
+            // Stop at the time limit rather than pressing past it
+            let wake = sched.press_deadline();
+            if run.time_reached(wake) {
+                if sleep_until(&sleeper, run.deadline.unwrap(), active) {
+                    running.store(false, Ordering::SeqCst);
+                    report.status("time limit reached");
+                }
+                continue;
+            }
+
+            if !sleep_until(&sleeper, wake, active) {
+                continue;
+            }
 
-                // Idle
-                if off_time > 0.0 {
-                    sleeper.sleep(Duration::from_secs_f64(off_time));
+            // Focus may have moved while we slept; the top of the loop
+            // pauses or stops
+            if s.focus_lock.enabled && focus.as_mut().is_some_and(|w| Some(w.active()) != locked) {
+                continue;
+            }
+
+            // Panic corner: checked before the pointer goes to a target
+            if s.panic_corner.enabled && corner_watch.is_none() {
+                match CornerWatch::new() {
+                    Ok(w) => corner_watch = Some(w),
+                    Err(e) => {
+                        running.store(false, Ordering::SeqCst);
+                        report.error(WorkerError::PanicCorner(format!("{e:#}")));
+                        continue;
+                    }
                 }
+            }
+            let in_corner = || s.panic_corner.enabled && corner_watch.as_ref().is_some_and(|w| w.hit(&s.panic_corner));
+            let corner_stop = || {
+                running.store(false, Ordering::SeqCst);
+                report.status(format!("stopped: pointer in the {} corner", s.panic_corner.corner.name()));
+            };
+            if in_corner() {
+                corner_stop();
+                continue;
+            }
+
+            // Move to the target point, remembering where the user was
+            let restore_to = match cycler.pick(&s.targets, &mut humanizer) {
+                Some(p) => {
+                    let home = if s.targets.restore { out.position() } else { None };
+                    if let Err(e) = out.move_to(p) {
+                        fail(e);
+                        continue;
+                    }
+                    home
+                }
+                None => None,
+            };
+
+            let released = if s.scroll.enabled {
+                // Turn the wheel instead of pressing a button
+                let mut result = scroll_once(&mut *out, &s.scroll);
+                if let Some(home) = restore_to {
+                    result = result.and_then(|_| out.move_to(home));
+                }
+                out.flush();
+                if let Err(e) = result {
+                    fail(e);
+                    continue;
+                }
+                let now = Instant::now();
+                stats.lock().unwrap().record_scroll(sched.press_deadline(), now);
+                now
             } else {
-                sleeper.sleep(Duration::from_millis(5));
+                // Press
+                if let Err(e) = press.down(&mut *out) {
+                    fail(e);
+                    continue;
+                }
+                out.flush();
+                last = press.clone();
+                let pressed = Instant::now();
+                stats.lock().unwrap().record_press(sched.press_deadline(), pressed);
+
+                // Hold until the planned release, but never shorter than
+                // MIN_PRESS when we woke up late.
+                let min_hold = Duration::from_secs_f64(ClickTiming::MIN_PRESS.min(sched.cycle_on_time()));
+                let release_at = sched.release_deadline().max(pressed + min_hold);
+                // A normal stop lets the click finish; a kill or the panic
+                // corner cuts it short.
+                let held = sleep_until(&sleeper, release_at, || !should_exit.load(Ordering::SeqCst) && !in_corner());
+                let panicked = !held && !should_exit.load(Ordering::SeqCst);
+
+                // Release
+                let mut result = press.up(&mut *out);
+                if let Some(home) = restore_to {
+                    result = result.and_then(|_| out.move_to(home));
+                }
+                out.flush();
+                if let Err(e) = result {
+                    fail(e);
+                    continue;
+                }
+                let released = Instant::now();
+                stats.lock().unwrap().record_release(sched.release_deadline(), released);
+                if panicked {
+                    corner_stop();
+                    continue;
+                }
+                released
+            };
+
+            sched.advance(released);
+
+            completed += 1;
+            if run.clicks_reached(completed) {
+                running.store(false, Ordering::SeqCst);
+                report.status("click limit reached");
             }
+        } else {
+            sched = None;
+            locked = None;
+            paused = false;
+            sleeper.sleep(Duration::from_millis(5));
         }
+    }
 
-        // Safety: ensure released
-        XTestFakeButtonEvent(dpy, last_button, False, CurrentTime);
-        XFlush(dpy);
-        XCloseDisplay(dpy);
+    // Safety: ensure released
+    if let Some(out) = &mut out {
+        let _ = last.up(&mut **out);
+        out.flush();
     }
     Ok(())
 }
@@ -211,18 +1244,237 @@ fn click_thread(
 // ---------- GUI app ----------
 struct GuiApp {
     settings: Arc<Mutex<Settings>>,
+    profiles: Arc<Mutex<Profiles>>,
     running: Arc<AtomicBool>,
     should_exit: Arc<AtomicBool>,
+    stats: Arc<Mutex<ClickStats>>,
     last_err: Option<String>,
+    // Status and errors reported by the worker threads
+    events: mpsc::Receiver<Event>,
+    worker_status: Option<String>,
+    // Hotkey another client holds, and free keys offered in its place
+    conflict: Option<(String, Vec<String>)>,
+    // Wakes hotkey_thread when hotkey_inputs differ from what it last saw
+    wakeup: Arc<Wakeup>,
+    hotkey_inputs: HotkeyInputs,
+    // Last state written to the config file, and when it started to differ
+    saved: Profiles,
+    // The config file failed to load; move it aside before the first save
+    keep_config: bool,
+    dirty_since: Option<Instant>,
+    // New name being typed while renaming the active profile
+    renaming: Option<String>,
+    // Pending "pick point" pointer grab
+    picking: Option<picker::Pending<(i32, i32)>>,
+    // Pending "pick window" pointer grab
+    picking_window: Option<picker::Pending<TargetWindow>>,
+    hotkeys: HotkeyEditor,
 }
 
 impl GuiApp {
-    fn new() -> Self {
+    /// Coalesce rapid edits (e.g. dragging a value) into one write.
+    const SAVE_DELAY: Duration = Duration::from_secs(1);
+
+    fn new(events: mpsc::Receiver<Event>, wakeup: Arc<Wakeup>) -> Self {
+        let mut last_err = None;
+        let mut keep_config = false;
+        let profiles = match config::load() {
+            Ok(p) => p.unwrap_or_default(),
+            Err(e) => {
+                last_err = Some(format!("Config not loaded, using defaults (it is kept on save): {e:#}"));
+                keep_config = true;
+                Profiles::default()
+            }
+        };
+        let settings = profiles.active().settings.clone();
         Self {
-            settings: Arc::new(Mutex::new(Settings::default())),
+            saved: profiles.clone(),
+            keep_config,
+            settings: Arc::new(Mutex::new(settings)),
+            profiles: Arc::new(Mutex::new(profiles)),
             running: Arc::new(AtomicBool::new(false)),
             should_exit: Arc::new(AtomicBool::new(false)),
-            last_err: None,
+            stats: Arc::new(Mutex::new(ClickStats::default())),
+            last_err,
+            events,
+            worker_status: None,
+            conflict: None,
+            wakeup,
+            hotkey_inputs: HotkeyInputs::default(),
+            dirty_since: None,
+            renaming: None,
+            picking: None,
+            picking_window: None,
+            hotkeys: HotkeyEditor::default(),
+        }
+    }
+
+    /// Profiles with the live settings folded into the active one.
+    fn snapshot(&self) -> Profiles {
+        let mut p = self.profiles.lock().unwrap().clone();
+        p.sync(&self.settings.lock().unwrap());
+        p
+    }
+
+    /// Write `current` to the config file, first moving a file that didn't
+    /// load out of the way.
+    fn save(&mut self, current: &Profiles) -> Result<()> {
+        if self.keep_config {
+            let bad = config::set_aside()?;
+            self.last_err = Some(format!("Old config kept as {}", bad.display()));
+            self.keep_config = false;
+        }
+        config::save(current)
+    }
+
+    /// Write profiles to disk once they have differed for SAVE_DELAY.
+    fn autosave(&mut self) {
+        let current = self.snapshot();
+        if current == self.saved {
+            self.dirty_since = None;
+            return;
+        }
+        let since = *self.dirty_since.get_or_insert_with(Instant::now);
+        if since.elapsed() < Self::SAVE_DELAY {
+            return;
+        }
+        if let Err(e) = self.save(&current) {
+            self.last_err = Some(format!("Config not saved: {e:#}"));
+        }
+        self.saved = current;
+        self.dirty_since = None;
+    }
+}
+
+impl GuiApp {
+    /// Take in everything the worker threads reported since the last frame.
+    fn poll_events(&mut self) {
+        while let Ok(ev) = self.events.try_recv() {
+            match &ev {
+                Event::Status(..) => self.worker_status = Some(ev.to_string()),
+                Event::Error(_, e) => {
+                    if let WorkerError::GrabConflict { key, suggestions, .. } = e {
+                        self.conflict = Some((key.clone(), suggestions.clone()));
+                    }
+                    self.last_err = Some(ev.to_string());
+                }
+            }
+        }
+    }
+
+    /// Rebind whichever hotkey (main, start/stop/kill or a profile's) is
+    /// set to `old`.
+    fn replace_hotkey(&mut self, old: &str, new: &str) {
+        let mut p = self.profiles.lock().unwrap();
+        let mut s = self.settings.lock().unwrap();
+        let s = &mut *s;
+        let fields = [&mut s.hotkey, &mut s.start_hotkey, &mut s.stop_hotkey, &mut s.kill_hotkey];
+        let profile_keys = p.list.iter_mut().map(|p| &mut p.hotkey);
+        for key in fields.into_iter().chain(profile_keys) {
+            if key == old {
+                *key = new.to_string();
+            }
+        }
+    }
+
+    /// Add the point from a finished "pick point" grab.
+    fn poll_picker(&mut self) {
+        let Some(rx) = &self.picking else { return };
+        let result = match rx.try_recv() {
+            Ok(r) => r,
+            Err(mpsc::TryRecvError::Empty) => return,
+            Err(mpsc::TryRecvError::Disconnected) => Ok(None),
+        };
+        self.picking = None;
+        match result {
+            Ok(Some((x, y))) => {
+                let mut s = self.settings.lock().unwrap();
+                s.targets.points.push(Point { x, y });
+                s.targets.enabled = true;
+            }
+            Ok(None) => {}
+            Err(e) => self.last_err = Some(format!("{e:#}")),
+        }
+    }
+
+    /// Switch to a window picked with "Pick window".
+    fn poll_window_picker(&mut self) {
+        let Some(rx) = &self.picking_window else { return };
+        let result = match rx.try_recv() {
+            Ok(r) => r,
+            Err(mpsc::TryRecvError::Empty) => return,
+            Err(mpsc::TryRecvError::Disconnected) => Ok(None),
+        };
+        self.picking_window = None;
+        match result {
+            Ok(Some(w)) => {
+                let mut s = self.settings.lock().unwrap();
+                s.window = w;
+                s.click_backend = BackendKind::Window;
+            }
+            Ok(None) => {}
+            Err(e) => self.last_err = Some(format!("{e:#}")),
+        }
+    }
+
+    fn profiles_ui(&mut self, ui: &mut egui::Ui) {
+        let mut p = self.profiles.lock().unwrap();
+        let mut s = self.settings.lock().unwrap();
+        let mut result = Ok(());
+
+        ui.horizontal(|ui| {
+            ui.label("Profile:");
+            if let Some(name) = &mut self.renaming {
+                ui.text_edit_singleline(name);
+                if ui.button("OK").clicked() {
+                    let active = p.active.clone();
+                    result = p.rename(&active, name);
+                    self.renaming = None;
+                }
+                if ui.button("Cancel").clicked() {
+                    self.renaming = None;
+                }
+                return;
+            }
+
+            let mut selected = p.active.clone();
+            egui::ComboBox::from_id_source("profile_combo")
+                .selected_text(selected.clone())
+                .show_ui(ui, |ui| {
+                    for name in p.list.iter().map(|q| q.name.clone()) {
+                        ui.selectable_value(&mut selected, name.clone(), name);
+                    }
+                });
+            if selected != p.active {
+                result = p.switch(&selected, &mut s);
+            }
+
+            if ui.button("New").clicked() {
+                p.create(&mut s);
+            }
+            if ui.button("Duplicate").clicked() {
+                p.duplicate(&mut s);
+            }
+            if ui.button("Rename").clicked() {
+                self.renaming = Some(p.active.clone());
+            }
+            let can_delete = p.list.len() > 1;
+            if ui.add_enabled(can_delete, egui::Button::new("Delete")).clicked() {
+                let active = p.active.clone();
+                result = p.delete(&active, &mut s);
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("Profile hotkey (select + start):");
+            let i = p.active_index();
+            if let Some(e) = self.hotkeys.show(ui, "profile", &mut p.list[i].hotkey, true, false) {
+                result = Err(anyhow::anyhow!(e));
+            }
+        });
+
+        if let Err(e) = result {
+            self.last_err = Some(format!("{e:#}"));
         }
     }
 }
@@ -231,14 +1483,27 @@ impl Drop for GuiApp {
     fn drop(&mut self) {
         self.should_exit.store(true, Ordering::SeqCst);
         self.running.store(false, Ordering::SeqCst);
+        self.wakeup.notify();
+
+        // Flush edits still waiting for SAVE_DELAY
+        let current = self.snapshot();
+        if current != self.saved {
+            if let Err(e) = self.save(&current) {
+                eprintln!("config save error: {e:#}");
+            }
+        }
     }
 }
 
 impl eframe::App for GuiApp {
     fn update(&mut self, ctx: &egui::Context, _: &mut eframe::Frame) {
+        self.poll_events();
         egui::CentralPanel::default().show(ctx, |ui| {
             ui.heading("X11 Autoclicker (decimal CPS & duty)");
 
+            self.profiles_ui(ui);
+            ui.separator();
+
             // Editable controls
             {
                 let mut s = self.settings.lock().unwrap();
@@ -269,36 +1534,318 @@ impl eframe::App for GuiApp {
                             for b in ["left", "middle", "right"] {
                                 ui.selectable_value(&mut s.button_name, b.to_string(), b);
                             }
-                            for n in 4..=9 {
+                            // 4..7 are the wheel: see "Scroll mode"
+                            for n in 8..=9 {
                                 let t = n.to_string();
                                 ui.selectable_value(&mut s.button_name, t.clone(), &t);
                             }
                         });
+                    ui.label("or key:");
+                    if let Some(e) = self.hotkeys.show(ui, "press_key", &mut s.press_key, true, true) {
+                        self.last_err = Some(e);
+                    }
+                    ui.label("via");
+                    egui::ComboBox::from_id_source("backend_combo")
+                        .selected_text(s.click_backend.name())
+                        .show_ui(ui, |ui| {
+                            for k in BackendKind::ALL {
+                                ui.selectable_value(&mut s.click_backend, k, k.name());
+                            }
+                        })
+                        .response
+                        .on_hover_text("xtest: through the X server\nuinput: virtual kernel device, also on Wayland\nwindow: sent to the target window, pointer untouched\nrecord: dry run, nothing is clicked");
                 });
 
                 ui.horizontal(|ui| {
-                    ui.label("Toggle hotkey (X11 keysym):");
-                    ui.text_edit_singleline(&mut s.hotkey);
+                    ui.label("Hotkey (e.g. F6, Ctrl+Shift+c, Button8):");
+                    if let Some(e) = self.hotkeys.show(ui, "main", &mut s.hotkey, false, false) {
+                        self.last_err = Some(e);
+                    }
+                });
+
+                ui.collapsing("More hotkeys", |ui| {
+                    let s = &mut *s;
+                    for (label, id, key) in [
+                        ("Start:", "start", &mut s.start_hotkey),
+                        ("Stop:", "stop", &mut s.stop_hotkey),
+                        ("Emergency kill (stop + quit):", "kill", &mut s.kill_hotkey),
+                    ] {
+                        ui.horizontal(|ui| {
+                            ui.label(label);
+                            if let Some(e) = self.hotkeys.show(ui, id, key, true, false) {
+                                self.last_err = Some(e);
+                            }
+                        });
+                    }
+                    ui.horizontal(|ui| {
+                        ui.label("Listen by:");
+                        ui.radio_value(&mut s.hotkey_backend, HotkeyBackend::Grab, "grabbing keys")
+                            .on_hover_text("Other applications don't see the hotkeys");
+                        ui.radio_value(&mut s.hotkey_backend, HotkeyBackend::Raw, "watching (XInput2)")
+                            .on_hover_text("Hotkeys also reach other applications and work while a game grabs the keyboard");
+                    });
+                });
+
+                ui.horizontal(|ui| {
+                    ui.label("Hotkey mode:");
+                    ui.radio_value(&mut s.activation, Activation::Toggle, "toggle");
+                    ui.radio_value(&mut s.activation, Activation::Hold, "hold");
+                    ui.radio_value(&mut s.activation, Activation::Burst, "burst of");
+                    ui.add_enabled(
+                        s.activation == Activation::Burst,
+                        egui::DragValue::new(&mut s.burst_clicks).clamp_range(1..=1_000_000).suffix(" clicks"),
+                    );
+                });
+
+                ui.collapsing("Stop conditions", |ui| {
+                    let l = &mut s.limits;
+                    ui.horizontal(|ui| {
+                        let mut on = l.max_clicks.is_some();
+                        ui.checkbox(&mut on, "After clicks:");
+                        if on != l.max_clicks.is_some() {
+                            l.max_clicks = on.then_some(100);
+                        }
+                        if let Some(n) = &mut l.max_clicks {
+                            ui.add(egui::DragValue::new(n).clamp_range(1..=1_000_000_000));
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        let mut on = l.max_secs.is_some();
+                        ui.checkbox(&mut on, "After duration:");
+                        if on != l.max_secs.is_some() {
+                            l.max_secs = on.then_some(60.0);
+                        }
+                        if let Some(secs) = &mut l.max_secs {
+                            ui.add(
+                                egui::DragValue::new(secs)
+                                    .speed(0.1)
+                                    .clamp_range(0.001..=86_400.0 * 365.0)
+                                    .suffix(" s"),
+                            );
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        let mut on = l.stop_at.is_some();
+                        ui.checkbox(&mut on, "At time of day:");
+                        if on != l.stop_at.is_some() {
+                            l.stop_at = on.then(|| "12:00".to_string());
+                        }
+                        if let Some(t) = &mut l.stop_at {
+                            ui.add(egui::TextEdit::singleline(t).desired_width(80.0).hint_text("HH:MM[:SS]"));
+                            if let Err(e) = limits::parse_time_of_day(t) {
+                                ui.colored_label(egui::Color32::RED, e.to_string());
+                            }
+                        }
+                    });
+                });
+
+                ui.collapsing("Scroll mode", |ui| {
+                    let sc = &mut s.scroll;
+                    ui.checkbox(&mut sc.enabled, "Scroll instead of clicking (rate and duty above are not used)");
+                    ui.add_enabled_ui(sc.enabled, |ui| {
+                        ui.horizontal(|ui| {
+                            ui.label("Direction:");
+                            for d in ScrollDirection::ALL {
+                                ui.radio_value(&mut sc.direction, d, d.name());
+                            }
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Ticks per second:");
+                            ui.add(egui::DragValue::new(&mut sc.rate).speed(0.1).clamp_range(0.1..=1000.0));
+                            ui.add_enabled_ui(!sc.smooth, |ui| {
+                                ui.label("per burst:");
+                                ui.add(egui::DragValue::new(&mut sc.burst).clamp_range(1..=100));
+                            });
+                        });
+                        ui.checkbox(&mut sc.smooth, "Smooth (high-resolution wheel, uinput backend only)");
+                    });
+                });
+
+                ui.collapsing("Click targets", |ui| {
+                    let t = &mut s.targets;
+                    ui.checkbox(&mut t.enabled, "Move the pointer to these points before each click");
+                    let mut remove = None;
+                    for (i, p) in t.points.iter_mut().enumerate() {
+                        ui.horizontal(|ui| {
+                            ui.label(format!("#{}", i + 1));
+                            ui.add(egui::DragValue::new(&mut p.x).prefix("x "));
+                            ui.add(egui::DragValue::new(&mut p.y).prefix("y "));
+                            if ui.small_button("✖").clicked() {
+                                remove = Some(i);
+                            }
+                        });
+                    }
+                    if let Some(i) = remove {
+                        t.points.remove(i);
+                    }
+                    ui.horizontal(|ui| {
+                        if self.picking.is_some() {
+                            ui.label("Left-click the point to add (other button cancels)…");
+                        } else if ui.button("Pick point").clicked() {
+                            self.picking = Some(picker::pick_point());
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Order:");
+                        ui.radio_value(&mut t.order, TargetOrder::Sequential, "in order");
+                        ui.radio_value(&mut t.order, TargetOrder::Random, "random");
+                    });
+                    ui.checkbox(&mut t.restore, "Restore the pointer after each click");
+                });
+
+                ui.collapsing("Target window", |ui| {
+                    let w = &mut s.window;
+                    if w.id == 0 {
+                        ui.label("No window picked.");
+                    } else {
+                        ui.label(format!("{} — {} (0x{:x})", w.class, w.title, w.id));
+                        ui.horizontal(|ui| {
+                            ui.label("Click at");
+                            ui.add(egui::DragValue::new(&mut w.at.x).prefix("x "));
+                            ui.add(egui::DragValue::new(&mut w.at.y).prefix("y "));
+                        });
+                    }
+                    ui.horizontal(|ui| {
+                        if self.picking_window.is_some() {
+                            ui.label("Left-click the window and point to click (other button cancels)…");
+                        } else if ui.button("Pick window").clicked() {
+                            self.picking_window = Some(picker::pick_window());
+                        }
+                    });
+                    ui.small("Clicks go to this window via \"window\" without moving the pointer. Some applications ignore synthetic (send_event) clicks, e.g. xterm by default and many games.");
+                });
+
+                ui.collapsing("Window lock", |ui| {
+                    let l = &mut s.focus_lock;
+                    ui.checkbox(&mut l.enabled, "Only click while the window active at start keeps focus");
+                    ui.horizontal(|ui| {
+                        ui.label("When it loses focus:");
+                        ui.radio_value(&mut l.on_leave, OnLeave::Pause, "pause until it's back");
+                        ui.radio_value(&mut l.on_leave, OnLeave::Stop, "stop");
+                    });
+                    ui.small("Start with the hotkey so the target window, not this one, is active.");
+                });
+
+                ui.collapsing("Takeover fail-safe", |ui| {
+                    let t = &mut s.takeover;
+                    ui.checkbox(&mut t.enabled, "Stop when I use the mouse");
+                    ui.add_enabled_ui(t.enabled, |ui| {
+                        ui.horizontal(|ui| {
+                            ui.label("Moved more than");
+                            ui.add(egui::DragValue::new(&mut t.distance).clamp_range(1.0..=2000.0).suffix(" px"));
+                        });
+                        ui.checkbox(&mut t.on_button, "Any mouse button pressed");
+                    });
+                    ui.small("Only real devices count, not the clicks and moves made here. Start with the hotkey, or moving to the target stops the run.");
+                });
+
+                ui.collapsing("Panic corner", |ui| {
+                    let c = &mut s.panic_corner;
+                    ui.checkbox(&mut c.enabled, "Stop at once when the pointer reaches a screen corner");
+                    ui.add_enabled_ui(c.enabled, |ui| {
+                        ui.horizontal(|ui| {
+                            egui::ComboBox::from_id_source("panic_corner")
+                                .selected_text(c.corner.name())
+                                .show_ui(ui, |ui| {
+                                    for k in Corner::ALL {
+                                        ui.selectable_value(&mut c.corner, k, k.name());
+                                    }
+                                });
+                            ui.label("zone");
+                            ui.add(egui::DragValue::new(&mut c.size).clamp_range(1..=200).suffix(" px"));
+                        });
+                    });
+                    ui.small("Keep click targets out of the zone, or they trigger it too.");
+                });
+
+                ui.collapsing("Humanization", |ui| {
+                    let h = &mut s.humanize;
+                    for (label, id, j) in [
+                        ("Period jitter:", "period_jitter", &mut h.period),
+                        ("Press jitter:", "press_jitter", &mut h.press),
+                    ] {
+                        ui.horizontal(|ui| {
+                            ui.label(label);
+                            egui::ComboBox::from_id_source(id)
+                                .selected_text(j.name())
+                                .show_ui(ui, |ui| {
+                                    for name in Jitter::NAMES {
+                                        if ui.selectable_label(j.name() == name, name).clicked() {
+                                            *j = j.with_name(name);
+                                        }
+                                    }
+                                });
+                            let what = if matches!(j, Jitter::Uniform { .. }) { "±" } else { "σ" };
+                            if let Some(w) = j.width_mut() {
+                                ui.label(what);
+                                ui.add(egui::DragValue::new(w).speed(0.1).clamp_range(0.0..=100.0).suffix(" %"));
+                            }
+                        });
+                    }
+                    ui.horizontal(|ui| {
+                        let mut fixed = h.seed.is_some();
+                        ui.checkbox(&mut fixed, "Fixed seed");
+                        if fixed != h.seed.is_some() {
+                            h.seed = fixed.then_some(0);
+                        }
+                        if let Some(seed) = &mut h.seed {
+                            ui.add(egui::DragValue::new(seed));
+                        }
+                    });
                 });
             }
 
             ui.separator();
 
             // Live timing
-            let (cps, duty) = {
-                let s = self.settings.lock().unwrap().clone();
-                (s.cps, s.duty)
+            let (t, h) = {
+                let s = self.settings.lock().unwrap();
+                (s.timing(), s.humanize.clone())
             };
-            if cps > 0.0 {
-                let period_ms = 1000.0 / cps;
-                let on_ms = (period_ms * (duty / 100.0)).max(1.0_f64.min(period_ms));
-                let off_ms = (period_ms - on_ms).max(0.0);
+            ui.label(format!(
+                "Period: {:.6} ms   |   Press (on): {:.6} ms   |   Release (off): {:.6} ms",
+                t.period * 1000.0,
+                t.on_time * 1000.0,
+                t.off_time() * 1000.0
+            ));
+            if !h.is_off() {
+                let range = |j: &Jitter, nominal: f64| {
+                    let (lo, hi) = j.range();
+                    format!("{:.3}–{:.3} ms ({})", nominal * lo * 1000.0, nominal * hi * 1000.0, j.name())
+                };
                 ui.label(format!(
-                    "Period: {:.6} ms   |   Press (on): {:.6} ms   |   Release (off): {:.6} ms",
-                    period_ms, on_ms, off_ms
+                    "Humanized (~95%):  period {}   |   press {}",
+                    range(&h.period, t.period),
+                    range(&h.press, t.on_time)
                 ));
             }
 
+            // Measured accuracy (what actually reached the X server)
+            let m = self.stats.lock().unwrap().summary();
+            ui.group(|ui| {
+                ui.label("Measured");
+                if m.clicks == 0 {
+                    ui.small("No clicks recorded yet.");
+                    return;
+                }
+                let fmt = |v: Option<f64>| v.map_or("–".to_string(), |v| format!("{v:.6}"));
+                ui.label(format!(
+                    "Clicks: {}   |   CPS: {}   |   Duty: {} %",
+                    m.clicks,
+                    fmt(m.cps),
+                    fmt(m.duty)
+                ));
+                ui.label(format!(
+                    "Jitter: mean {:.3} ms, σ {:.3} ms, p99 {:.3} ms   |   Late (>{} ms): {}/{}",
+                    m.jitter_mean_ms,
+                    m.jitter_stddev_ms,
+                    m.jitter_p99_ms,
+                    LATE_THRESHOLD_MS,
+                    m.late,
+                    m.events
+                ));
+            });
+
             ui.separator();
 
             // Start / Stop
@@ -316,47 +1863,149 @@ impl eframe::App for GuiApp {
                 ui.label(if running { "Status: RUNNING" } else { "Status: idle" });
             });
 
+            // Progress towards the stop conditions of the current run
+            let (completed, limit) = {
+                let st = self.stats.lock().unwrap();
+                (st.completed(), st.limit().cloned())
+            };
+            if let (true, Some(limit)) = (self.running.load(Ordering::SeqCst), limit) {
+                let now = Instant::now();
+                let mut left = Vec::new();
+                if let Some(n) = limit.remaining_clicks(completed) {
+                    left.push(format!("{n} clicks"));
+                }
+                if let Some(d) = limit.remaining_time(now) {
+                    left.push(format!("{:.1} s", d.as_secs_f64()));
+                }
+                let p = limit.progress(completed, now).unwrap_or(0.0);
+                ui.add(egui::ProgressBar::new(p).text(format!("{} left", left.join(" / "))));
+            }
+
+            if let Some(status) = &self.worker_status {
+                ui.small(status);
+            }
             if let Some(err) = &self.last_err {
-                ui.colored_label(egui::Color32::RED, format!("Error: {err}"));
+                let dismiss = ui
+                    .horizontal(|ui| {
+                        ui.colored_label(egui::Color32::RED, format!("Error: {err}"));
+                        ui.small_button("✖").on_hover_text("Dismiss").clicked()
+                    })
+                    .inner;
+                if dismiss {
+                    self.last_err = None;
+                    self.conflict = None;
+                }
+            }
+            if let Some((key, free)) = self.conflict.clone() {
+                ui.horizontal(|ui| {
+                    ui.label(format!("Use instead of {key}:"));
+                    for alt in &free {
+                        if ui.button(alt).clicked() {
+                            self.replace_hotkey(&key, alt);
+                            self.conflict = None;
+                            self.last_err = None;
+                        }
+                    }
+                    if free.is_empty() {
+                        ui.label("no free keys found");
+                    }
+                });
             }
 
             ui.separator();
             ui.small("Tip: Works on X11 only. Hover over the target window and press the hotkey (default F6) to toggle.");
         });
 
+        self.poll_picker();
+        self.poll_window_picker();
+        self.autosave();
+
+        // hotkey_thread only looks at the settings when woken
+        let inputs = hotkey_inputs(&self.profiles, &self.settings);
+        if inputs != self.hotkey_inputs {
+            self.hotkey_inputs = inputs;
+            self.wakeup.notify();
+        }
+
+        // Emergency kill hotkey: the workers are already gone
+        if self.should_exit.load(Ordering::SeqCst) {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+        }
+
         ctx.request_repaint_after(Duration::from_millis(50));
     }
 }
 
-fn main() -> Result<()> {
-    // Create app + spawn threads
-    let app = GuiApp::new();
-
-    {
-        let running = app.running.clone();
-        let should_exit = app.should_exit.clone();
-        let settings = app.settings.clone();
+/// Start hotkey_thread and click_thread on the shared state, in that order.
+/// Both report status and errors on `events`; notify `wakeup` after
+/// changing hotkeys or setting should_exit.
+fn spawn_workers(
+    running: &Arc<AtomicBool>,
+    should_exit: &Arc<AtomicBool>,
+    settings: &Arc<Mutex<Settings>>,
+    profiles: &Arc<Mutex<Profiles>>,
+    stats: &Arc<Mutex<ClickStats>>,
+    events: &mpsc::Sender<Event>,
+    wakeup: &Arc<Wakeup>,
+) -> (thread::JoinHandle<()>, thread::JoinHandle<()>, thread::JoinHandle<()>) {
+    let hotkey = {
+        let running = running.clone();
+        let should_exit = should_exit.clone();
+        let settings = settings.clone();
+        let profiles = profiles.clone();
+        let wakeup = wakeup.clone();
+        let report = Reporter::new(events.clone(), Worker::Hotkey);
         thread::spawn(move || {
-            if let Err(e) = hotkey_thread(running, should_exit, settings) {
-                eprintln!("hotkey thread error: {e}");
+            if let Err(e) = hotkey_thread(running, should_exit, settings, profiles, wakeup, report.clone()) {
+                report.error(WorkerError::Fatal(format!("{e:#}")));
             }
-        });
-    }
-    {
-        let running = app.running.clone();
-        let should_exit = app.should_exit.clone();
-        let settings = app.settings.clone();
+        })
+    };
+    let click = {
+        let running = running.clone();
+        let should_exit = should_exit.clone();
+        let settings = settings.clone();
+        let stats = stats.clone();
+        let report = Reporter::new(events.clone(), Worker::Click);
         thread::spawn(move || {
-            if let Err(e) = click_thread(running, should_exit, settings) {
-                eprintln!("click thread error: {e}");
+            if let Err(e) = click_thread(running, should_exit, settings, stats, report.clone()) {
+                report.error(WorkerError::Fatal(format!("{e:#}")));
             }
-        });
+        })
+    };
+    let takeover = {
+        let running = running.clone();
+        let should_exit = should_exit.clone();
+        let settings = settings.clone();
+        let report = Reporter::new(events.clone(), Worker::Takeover);
+        thread::spawn(move || {
+            if let Err(e) = takeover_thread(running, should_exit, settings, report.clone()) {
+                report.error(WorkerError::Fatal(format!("{e:#}")));
+            }
+        })
+    };
+    (hotkey, click, takeover)
+}
+
+fn main() -> Result<()> {
+    let args = match cli::parse(std::env::args().skip(1))? {
+        Some(args) => args,
+        None => return Ok(()), // --help
+    };
+    if args.headless {
+        return cli::run_headless(args);
     }
 
+    // Create app + spawn threads
+    let (tx, rx) = mpsc::channel();
+    let wakeup = Arc::new(Wakeup::new()?);
+    let app = GuiApp::new(rx, wakeup.clone());
+    spawn_workers(&app.running, &app.should_exit, &app.settings, &app.profiles, &app.stats, &tx, &wakeup);
+
     // Launch window (eframe 0.27)
     let options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
-            .with_inner_size(egui::vec2(520.0, 260.0)),
+            .with_inner_size(egui::vec2(560.0, 400.0)),
         ..Default::default()
     };
 
@@ -369,3 +2018,196 @@ fn main() -> Result<()> {
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use backend::{Action, Recording};
+
+    fn timing(period: f64) -> ClickTiming {
+        ClickTiming { period, on_time: period / 2.0 }
+    }
+
+    /// Seconds from `origin` to `t`.
+    fn since(origin: Instant, t: Instant) -> f64 {
+        t.duration_since(origin).as_secs_f64()
+    }
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-6
+    }
+
+    #[test]
+    fn scheduler_keeps_the_grid_when_on_time() {
+        let t0 = Instant::now();
+        let mut s = Scheduler::new(t0, timing(0.1));
+        for _ in 0..1000 {
+            let at = s.release_deadline();
+            s.advance(at);
+        }
+        // No drift: cycle n presses at exactly n periods
+        assert_eq!(s.cycle, 1000);
+        assert!(close(since(t0, s.press_deadline()), 100.0));
+    }
+
+    #[test]
+    fn scheduler_catches_up_small_delays() {
+        let t0 = Instant::now();
+        let mut s = Scheduler::new(t0, timing(0.1));
+        // 2.5 periods late for the next press: below MAX_CATCH_UP_CYCLES
+        s.advance(t0 + Duration::from_secs_f64(0.35));
+        assert_eq!(s.cycle, 1);
+        assert!(close(since(t0, s.press_deadline()), 0.1));
+        s.advance(t0 + Duration::from_secs_f64(0.35));
+        assert_eq!(s.cycle, 2);
+    }
+
+    #[test]
+    fn scheduler_skips_after_a_stall_and_rejoins_the_grid() {
+        let t0 = Instant::now();
+        let mut s = Scheduler::new(t0, timing(0.1));
+        let now = t0 + Duration::from_secs_f64(1.05);
+        s.advance(now);
+        // The first grid point not before `now`
+        assert_eq!(s.cycle, 11);
+        assert!(s.press_deadline() >= now);
+        assert!(close(since(t0, s.press_deadline()), 1.1));
+    }
+
+    #[test]
+    fn scheduler_threshold_is_max_catch_up_cycles() {
+        let t0 = Instant::now();
+        let mut s = Scheduler::new(t0, timing(0.1));
+        // Just under 3 periods behind the next press is still caught up
+        s.advance(t0 + Duration::from_secs_f64(0.39));
+        assert_eq!(s.cycle, 1);
+        let mut s = Scheduler::new(t0, timing(0.1));
+        s.advance(t0 + Duration::from_secs_f64(0.45));
+        assert_eq!(s.cycle, 5);
+    }
+
+    #[test]
+    fn scheduler_retime_keeps_the_pending_press() {
+        let t0 = Instant::now();
+        let mut s = Scheduler::new(t0, timing(0.1));
+        s.advance(t0 + Duration::from_secs_f64(0.1));
+        s.advance(t0 + Duration::from_secs_f64(0.2));
+        let pending = s.press_deadline();
+        s.retime(timing(0.5));
+        assert_eq!(s.press_deadline(), pending);
+        s.advance(pending);
+        assert!(close(since(pending, s.press_deadline()), 0.5));
+
+        // Unchanged timing is a no-op
+        let cycle = s.cycle;
+        s.retime(timing(0.5));
+        assert_eq!(s.cycle, cycle);
+    }
+
+    #[test]
+    fn scheduler_humanized_offsets_do_not_accumulate_drift() {
+        let t0 = Instant::now();
+        let mut s = Scheduler::new(t0, timing(0.1));
+        s.vary(1.2, 1.0);
+        s.advance(t0);
+        assert!(close(since(t0, s.press_deadline()), 0.12));
+        s.vary(0.8, 1.0);
+        s.advance(t0);
+        // +20 % then -20 %: back on the grid
+        assert!(close(since(t0, s.press_deadline()), 0.2));
+    }
+
+    #[test]
+    fn press_button_records_press_then_release() {
+        let mut out = Recording::default();
+        let p = Press::Button(3);
+        p.down(&mut out).unwrap();
+        p.up(&mut out).unwrap();
+        assert_eq!(out.actions, [Action::Press(3), Action::Release(3)]);
+    }
+
+    #[test]
+    fn press_chord_holds_modifiers_around_the_key() {
+        let mut out = Recording::default();
+        let p = Press::Keys(parse_press_key("Ctrl+Shift+e").unwrap());
+        p.down(&mut out).unwrap();
+        p.up(&mut out).unwrap();
+        let key = |k: &str, down| Action::Key { key: k.to_string(), down };
+        assert_eq!(
+            out.actions,
+            [
+                key("Control_L", true),
+                key("Shift_L", true),
+                key("e", true),
+                key("e", false),
+                key("Shift_L", false),
+                key("Control_L", false),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_button_rejects_the_wheel() {
+        assert_eq!(parse_button("Right").unwrap(), 3);
+        assert_eq!(parse_button("8").unwrap(), 8);
+        for b in ["0", "4", "5", "6", "7", "10", "back"] {
+            assert!(parse_button(b).is_err(), "{b}");
+        }
+    }
+
+    #[test]
+    fn press_key_rejects_mouse_buttons() {
+        assert!(parse_press_key("Button8").is_err());
+    }
+
+    #[test]
+    fn scroll_sends_bursts_in_the_chosen_direction() {
+        let mut out = Recording::default();
+        let mut sc = Scroll { enabled: true, direction: ScrollDirection::Down, burst: 3, ..Scroll::default() };
+        scroll_once(&mut out, &sc).unwrap();
+        sc.direction = ScrollDirection::Left;
+        sc.burst = 1;
+        scroll_once(&mut out, &sc).unwrap();
+        assert_eq!(out.actions, [Action::Scroll { dx: 0, dy: 3 }, Action::Scroll { dx: -1, dy: 0 }]);
+    }
+
+    #[test]
+    fn smooth_scroll_sends_hi_res_steps() {
+        let mut out = Recording::default();
+        let sc = Scroll { enabled: true, direction: ScrollDirection::Up, smooth: true, burst: 5, ..Scroll::default() };
+        for _ in 0..Scroll::SMOOTH_STEPS {
+            scroll_once(&mut out, &sc).unwrap();
+        }
+        // Burst is ignored; the steps add up to one tick
+        let total: i32 = out
+            .actions
+            .iter()
+            .map(|a| match a {
+                Action::ScrollHiRes { dx: 0, dy } => *dy,
+                other => panic!("unexpected {other:?}"),
+            })
+            .sum();
+        assert_eq!(total, -120);
+    }
+
+    #[test]
+    fn release_goes_to_the_binding_its_press_fired() {
+        let binding = |mods, action| Binding {
+            combo: KeyCombo { trigger: Trigger::Key(72), mods },
+            action,
+            text: String::new(),
+        };
+        // Kill comes first, as in Bindings::resolve
+        let mut b = Bindings {
+            keys: vec![binding(ControlMask, HotkeyAction::Kill), binding(0, HotkeyAction::Toggle)],
+            ..Bindings::default()
+        };
+        assert_eq!(b.lookup(Trigger::Key(72), 0, true), Some(HotkeyAction::Toggle));
+        assert_eq!(b.lookup(Trigger::Key(72), 0, false), Some(HotkeyAction::Toggle));
+        // Ctrl let go before the key: still the Kill binding
+        assert_eq!(b.lookup(Trigger::Key(72), ControlMask, true), Some(HotkeyAction::Kill));
+        assert_eq!(b.lookup(Trigger::Key(72), 0, false), Some(HotkeyAction::Kill));
+        // A release without a press we saw fires nothing
+        assert_eq!(b.lookup(Trigger::Key(72), 0, false), None);
+    }
+}
@@ -0,0 +1,258 @@
+// ---------- Interactive pickers ----------
+// Short-lived pointer/keyboard grabs run on their own thread and connection
+// so the GUI keeps repainting; the result comes back over a channel.
+
+use std::{
+    ptr,
+    sync::mpsc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::{bail, Result};
+use x11::xlib::*;
+
+use crate::{
+    accel::{Accel, RELEVANT_MODS},
+    targets::{Point, TargetWindow},
+};
+
+/// Outcome of a running picker: Ok(None) if the user cancelled.
+pub type Pending<T> = mpsc::Receiver<Result<Option<T>>>;
+
+/// How long a picker waits for the user before giving up.
+const PICK_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Start a pointer pick in the background: the next left click anywhere
+/// on screen yields its root coordinates; any other button cancels.
+pub fn pick_point() -> Pending<(i32, i32)> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let _ = tx.send(unsafe { grab_next_click(|_, b| Ok((b.x_root, b.y_root))) });
+    });
+    rx
+}
+
+/// Start a window pick in the background: the next left click selects
+/// the application window under the pointer and the clicked point in it.
+pub fn pick_window() -> Pending<TargetWindow> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let _ = tx.send(unsafe { grab_next_click(|dpy, b| window_at(dpy, b)) });
+    });
+    rx
+}
+
+unsafe fn window_at(dpy: *mut Display, b: &XButtonEvent) -> Result<TargetWindow> {
+    if b.subwindow == 0 {
+        bail!("Picker: that's the desktop, not a window");
+    }
+    let w = client_window(dpy, b.subwindow);
+    let (mut x, mut y, mut child) = (0, 0, 0);
+    XTranslateCoordinates(dpy, b.root, w, b.x_root, b.y_root, &mut x, &mut y, &mut child);
+    let (class, title) = window_names(dpy, w);
+    Ok(TargetWindow { id: w as u64, class, title, at: Point { x, y } })
+}
+
+/// The application window in or below the window manager frame `w`: the
+/// first one with WM_STATE set, or `w` itself if there is none.
+unsafe fn client_window(dpy: *mut Display, w: Window) -> Window {
+    let wm_state = XInternAtom(dpy, c"WM_STATE".as_ptr(), True);
+    if wm_state == 0 {
+        return w;
+    }
+    let mut queue = std::collections::VecDeque::from([w]);
+    while let Some(w) = queue.pop_front() {
+        if get_property(dpy, w, wm_state, AnyPropertyType as Atom).is_some() {
+            return w;
+        }
+        let (mut root, mut parent) = (0, 0);
+        let mut children = ptr::null_mut();
+        let mut n = 0;
+        if XQueryTree(dpy, w, &mut root, &mut parent, &mut children, &mut n) != 0 && !children.is_null() {
+            queue.extend(std::slice::from_raw_parts(children, n as usize));
+            XFree(children as *mut _);
+        }
+    }
+    w
+}
+
+/// Raw bytes of a window property, if set.
+unsafe fn get_property(dpy: *mut Display, w: Window, prop: Atom, ty: Atom) -> Option<Vec<u8>> {
+    let (mut actual, mut format, mut n, mut after) = (0, 0, 0, 0);
+    let mut data = ptr::null_mut();
+    let status = XGetWindowProperty(
+        dpy, w, prop, 0, 1024, False, ty, &mut actual, &mut format, &mut n, &mut after, &mut data,
+    );
+    if status != Success as i32 || actual == 0 || data.is_null() {
+        return None;
+    }
+    let len = n as usize * (format as usize / 8);
+    let bytes = std::slice::from_raw_parts(data, len).to_vec();
+    XFree(data as *mut _);
+    Some(bytes)
+}
+
+/// WM_CLASS class and the window title (_NET_WM_NAME, else WM_NAME).
+unsafe fn window_names(dpy: *mut Display, w: Window) -> (String, String) {
+    let text = |bytes: Vec<u8>| String::from_utf8_lossy(&bytes).trim_end_matches('\0').to_string();
+    // WM_CLASS is "instance\0class\0"
+    let class = get_property(dpy, w, XA_WM_CLASS, XA_STRING)
+        .map(|b| text(b).rsplit('\0').next().unwrap_or_default().to_string())
+        .unwrap_or_default();
+    let net_name = XInternAtom(dpy, c"_NET_WM_NAME".as_ptr(), False);
+    let utf8 = XInternAtom(dpy, c"UTF8_STRING".as_ptr(), False);
+    let title = get_property(dpy, w, net_name, utf8)
+        .or_else(|| get_property(dpy, w, XA_WM_NAME, XA_STRING))
+        .map(text)
+        .unwrap_or_default();
+    (class, title)
+}
+
+/// Grab the pointer until the next left click and turn it into a result
+/// with `on_click`; any other button cancels.
+unsafe fn grab_next_click<T>(on_click: impl FnOnce(*mut Display, &XButtonEvent) -> Result<T>) -> Result<Option<T>> {
+    let dpy = XOpenDisplay(ptr::null());
+    if dpy.is_null() {
+        bail!("Picker: failed to open X display (X11 only)");
+    }
+    let root = XDefaultRootWindow(dpy);
+    let cursor = XCreateFontCursor(dpy, 34); // XC_crosshair
+
+    let status = XGrabPointer(
+        dpy,
+        root,
+        False,
+        ButtonPressMask as u32,
+        GrabModeAsync,
+        GrabModeAsync,
+        0,
+        cursor,
+        CurrentTime,
+    );
+    if status != GrabSuccess {
+        XFreeCursor(dpy, cursor);
+        XCloseDisplay(dpy);
+        bail!("Picker: could not grab the pointer (another client holds it)");
+    }
+
+    let started = Instant::now();
+    let mut event: XEvent = std::mem::zeroed();
+    let mut result = Ok(None);
+    loop {
+        if XPending(dpy) > 0 {
+            XNextEvent(dpy, &mut event);
+            if event.get_type() == ButtonPress {
+                let b = event.button;
+                if b.button == Button1 {
+                    result = on_click(dpy, &b).map(Some);
+                }
+                break;
+            }
+        } else if started.elapsed() >= PICK_TIMEOUT {
+            result = Err(anyhow::anyhow!("Picker: timed out"));
+            break;
+        } else {
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    XUngrabPointer(dpy, CurrentTime);
+    XFreeCursor(dpy, cursor);
+    XFlush(dpy);
+    XCloseDisplay(dpy);
+    result
+}
+
+/// Start a key capture in the background: the next non-modifier key press
+/// or side mouse button press (with the modifiers held at the time) yields
+/// its canonical accelerator, e.g. "Ctrl+Shift+F6" or "Button8". Other
+/// clicks and the wheel are ignored. A bare Escape cancels.
+pub fn capture_key() -> Pending<String> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let _ = tx.send(unsafe { grab_next_key() });
+    });
+    rx
+}
+
+/// Shift_L..Hyper_R, ISO_Level3_Shift, Mode_switch, Num_Lock: keys that
+/// only modify others and can't be a hotkey on their own.
+fn is_modifier_keysym(ks: KeySym) -> bool {
+    (0xffe1..=0xffee).contains(&ks) || ks == 0xfe03 || ks == 0xff7e || ks == 0xff7f
+}
+
+unsafe fn grab_next_key() -> Result<Option<String>> {
+    const XK_ESCAPE: KeySym = 0xff1b;
+
+    let dpy = XOpenDisplay(ptr::null());
+    if dpy.is_null() {
+        bail!("Key capture: failed to open X display (X11 only)");
+    }
+    let root = XDefaultRootWindow(dpy);
+    let status = XGrabKeyboard(dpy, root, False, GrabModeAsync, GrabModeAsync, CurrentTime);
+    if status != GrabSuccess {
+        XCloseDisplay(dpy);
+        bail!("Key capture: could not grab the keyboard (another client holds it)");
+    }
+    // Mouse buttons are optional: without the pointer grab only keys count
+    let mask = ButtonPressMask as u32;
+    XGrabPointer(dpy, root, False, mask, GrabModeAsync, GrabModeAsync, 0, 0, CurrentTime);
+
+    let started = Instant::now();
+    let mut event: XEvent = std::mem::zeroed();
+    let result = loop {
+        if XPending(dpy) > 0 {
+            XNextEvent(dpy, &mut event);
+            if event.get_type() == ButtonPress {
+                let b = event.button;
+                // A click or a scroll while waiting doesn't bind anything
+                if !Accel::BUTTONS.contains(&b.button) {
+                    continue;
+                }
+                let accel = Accel {
+                    mods: b.state & RELEVANT_MODS,
+                    key: format!("Button{}", b.button),
+                };
+                break Ok(Some(accel.to_string()));
+            }
+            if event.get_type() != KeyPress {
+                continue;
+            }
+            let key = event.key;
+            // Level 0 keysym: Shift is reported as a modifier, not as "C"
+            let ks = XkbKeycodeToKeysym(dpy, key.keycode as u8, 0, 0);
+            if ks == 0 || is_modifier_keysym(ks) {
+                continue;
+            }
+            let mods = key.state & RELEVANT_MODS;
+            if ks == XK_ESCAPE && mods == 0 {
+                break Ok(None);
+            }
+            let name = XKeysymToString(ks);
+            if name.is_null() {
+                break Err(anyhow::anyhow!("Key capture: keycode {} has no keysym name", key.keycode));
+            }
+            let accel = Accel {
+                mods,
+                key: std::ffi::CStr::from_ptr(name).to_string_lossy().into_owned(),
+            };
+            // The name must lead back to the same key, or the grab would
+            // land somewhere else.
+            if crate::keysym_to_keycode(dpy, &accel.key).ok() != Some(key.keycode) {
+                break Err(anyhow::anyhow!("Key capture: '{accel}' does not map back to the pressed key"));
+            }
+            break Ok(Some(accel.to_string()));
+        } else if started.elapsed() >= PICK_TIMEOUT {
+            break Err(anyhow::anyhow!("Key capture: timed out"));
+        } else {
+            thread::sleep(Duration::from_millis(10));
+        }
+    };
+
+    XUngrabKeyboard(dpy, CurrentTime);
+    XUngrabPointer(dpy, CurrentTime);
+    XFlush(dpy);
+    XCloseDisplay(dpy);
+    result
+}
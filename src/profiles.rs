@@ -0,0 +1,168 @@
+// ---------- Profiles ----------
+// Named presets of Settings. The active profile is the one being edited:
+// its live copy is the shared Arc<Mutex<Settings>> the worker threads read,
+// and `sync` folds that copy back into the list before switching or saving.
+//
+// Lock order when both are needed: Profiles first, then Settings.
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::Settings;
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct Profile {
+    pub name: String,
+    /// Optional accelerator (e.g. "Ctrl+F9") that selects this profile and
+    /// starts clicking.
+    #[serde(default)]
+    pub hotkey: String,
+    #[serde(default)]
+    pub settings: Settings,
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct Profiles {
+    pub active: String,
+    #[serde(rename = "profile")]
+    pub list: Vec<Profile>,
+}
+
+impl Default for Profiles {
+    fn default() -> Self {
+        Self::from_settings(Settings::default())
+    }
+}
+
+impl Profiles {
+    pub const DEFAULT_NAME: &'static str = "Default";
+
+    pub fn from_settings(settings: Settings) -> Self {
+        Self {
+            active: Self::DEFAULT_NAME.to_string(),
+            list: vec![Profile {
+                name: Self::DEFAULT_NAME.to_string(),
+                hotkey: String::new(),
+                settings,
+            }],
+        }
+    }
+
+    /// Repair a loaded set: at least one profile, and `active` names one.
+    pub fn normalized(mut self) -> Self {
+        if self.list.is_empty() {
+            return Self::default();
+        }
+        if self.index_of(&self.active).is_none() {
+            self.active = self.list[0].name.clone();
+        }
+        self
+    }
+
+    pub fn index_of(&self, name: &str) -> Option<usize> {
+        self.list.iter().position(|p| p.name == name)
+    }
+
+    pub fn active_index(&self) -> usize {
+        self.index_of(&self.active).unwrap_or(0)
+    }
+
+    pub fn active(&self) -> &Profile {
+        &self.list[self.active_index()]
+    }
+
+    /// Store the live settings into the active profile.
+    pub fn sync(&mut self, live: &Settings) {
+        let i = self.active_index();
+        self.list[i].settings = live.clone();
+    }
+
+    /// Make `name` active, saving `live` into the previous profile and
+    /// loading the new one into it.
+    pub fn switch(&mut self, name: &str, live: &mut Settings) -> Result<()> {
+        let Some(i) = self.index_of(name) else {
+            bail!("no profile named '{name}'");
+        };
+        self.sync(live);
+        self.active = name.to_string();
+        *live = self.list[i].settings.clone();
+        Ok(())
+    }
+
+    /// `base`, or `base (2)`, `base (3)`... whichever is free.
+    pub fn unique_name(&self, base: &str) -> String {
+        let base = base.trim();
+        let base = if base.is_empty() { "Profile" } else { base };
+        if self.index_of(base).is_none() {
+            return base.to_string();
+        }
+        (2..)
+            .map(|n| format!("{base} ({n})"))
+            .find(|n| self.index_of(n).is_none())
+            .unwrap()
+    }
+
+    /// Add a profile with default settings and make it active.
+    pub fn create(&mut self, live: &mut Settings) -> String {
+        let name = self.unique_name("Profile");
+        self.list.push(Profile {
+            name: name.clone(),
+            hotkey: String::new(),
+            settings: Settings::default(),
+        });
+        self.switch(&name, live).unwrap();
+        name
+    }
+
+    /// Copy the active profile (with its live settings) and make the copy active.
+    pub fn duplicate(&mut self, live: &mut Settings) -> String {
+        self.sync(live);
+        let src = self.active().clone();
+        let name = self.unique_name(&format!("{} copy", src.name));
+        self.list.push(Profile {
+            name: name.clone(),
+            // Two profiles can't share one trigger key
+            hotkey: String::new(),
+            settings: src.settings,
+        });
+        self.switch(&name, live).unwrap();
+        name
+    }
+
+    pub fn rename(&mut self, from: &str, to: &str) -> Result<()> {
+        let to = to.trim();
+        if to.is_empty() {
+            bail!("profile name can't be empty");
+        }
+        let Some(i) = self.index_of(from) else {
+            bail!("no profile named '{from}'");
+        };
+        if from != to && self.index_of(to).is_some() {
+            bail!("a profile named '{to}' already exists");
+        }
+        self.list[i].name = to.to_string();
+        if self.active == from {
+            self.active = to.to_string();
+        }
+        Ok(())
+    }
+
+    /// Remove a profile. The last one can't be deleted; deleting the active
+    /// one activates its neighbour.
+    pub fn delete(&mut self, name: &str, live: &mut Settings) -> Result<()> {
+        if self.list.len() <= 1 {
+            bail!("can't delete the last profile");
+        }
+        let Some(i) = self.index_of(name) else {
+            bail!("no profile named '{name}'");
+        };
+        let was_active = self.active == name;
+        self.list.remove(i);
+        if was_active {
+            let next = &self.list[i.min(self.list.len() - 1)];
+            self.active = next.name.clone();
+            *live = next.settings.clone();
+        }
+        Ok(())
+    }
+}
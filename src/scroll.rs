@@ -0,0 +1,88 @@
+// ---------- Scroll mode ----------
+// Instead of pressing a button, click_thread turns the wheel: `burst`
+// ticks at a time, `rate` ticks per second on average. Smooth scrolling
+// sends each tick as small high-resolution steps (uinput only).
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScrollDirection {
+    Up,
+    #[default]
+    Down,
+    Left,
+    Right,
+}
+
+impl ScrollDirection {
+    pub const ALL: [ScrollDirection; 4] =
+        [ScrollDirection::Up, ScrollDirection::Down, ScrollDirection::Left, ScrollDirection::Right];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ScrollDirection::Up => "up",
+            ScrollDirection::Down => "down",
+            ScrollDirection::Left => "left",
+            ScrollDirection::Right => "right",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|d| d.name() == name)
+    }
+
+    /// One tick as (dx, dy) in ClickBackend::scroll terms.
+    pub fn delta(self) -> (i32, i32) {
+        match self {
+            ScrollDirection::Up => (0, -1),
+            ScrollDirection::Down => (0, 1),
+            ScrollDirection::Left => (-1, 0),
+            ScrollDirection::Right => (1, 0),
+        }
+    }
+
+    /// The core X button the wheel turns with (4 up, 5 down, 6 left, 7 right).
+    pub fn button(self) -> u32 {
+        match self {
+            ScrollDirection::Up => 4,
+            ScrollDirection::Down => 5,
+            ScrollDirection::Left => 6,
+            ScrollDirection::Right => 7,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Scroll {
+    pub enabled: bool,
+    pub direction: ScrollDirection,
+    /// Ticks per second.
+    pub rate: f64,
+    /// Ticks sent together; ignored when smooth.
+    pub burst: u32,
+    pub smooth: bool,
+}
+
+impl Default for Scroll {
+    fn default() -> Self {
+        Self { enabled: false, direction: ScrollDirection::Down, rate: 10.0, burst: 1, smooth: false }
+    }
+}
+
+impl Scroll {
+    /// High-resolution steps per tick when smooth; evdev counts 120 units
+    /// per tick, so each step is 15.
+    pub const SMOOTH_STEPS: u32 = 8;
+
+    /// Seconds between two scroll events.
+    pub fn period(&self) -> f64 {
+        let rate = if self.rate > 0.0 { self.rate } else { 0.1 };
+        if self.smooth {
+            1.0 / (rate * Self::SMOOTH_STEPS as f64)
+        } else {
+            self.burst.max(1) as f64 / rate
+        }
+    }
+}
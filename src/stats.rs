@@ -0,0 +1,152 @@
+// ---------- Measured timing statistics ----------
+// click_thread records when each press/release actually reached the X
+// server (right after XFlush) next to the deadline it was planned for;
+// the GUI reads a summary of that for the accuracy panel.
+
+use std::{collections::VecDeque, time::Instant};
+
+use crate::limits::RunLimit;
+
+/// An event counts as late when it lands this long after its deadline.
+pub const LATE_THRESHOLD_MS: f64 = 1.0;
+
+/// Jitter samples kept for the p99 estimate (press + release events).
+const RECENT_SAMPLES: usize = 4096;
+
+#[derive(Default)]
+pub struct ClickStats {
+    clicks: u64,
+    first_press: Option<Instant>,
+    last_press: Option<Instant>,
+    pending_press: Option<Instant>,
+    held_secs: f64,
+    // Scroll events: counted as clicks, but nothing is held
+    scrolls: u64,
+    late: u64,
+    // Welford running mean/variance of lateness in ms
+    events: u64,
+    mean: f64,
+    m2: f64,
+    recent: VecDeque<f64>,
+    // Stop conditions of the current run, for the progress display
+    limit: Option<RunLimit>,
+}
+
+/// Snapshot of ClickStats for display.
+pub struct StatsSummary {
+    pub clicks: u64,
+    pub cps: Option<f64>,
+    pub duty: Option<f64>,
+    pub jitter_mean_ms: f64,
+    pub jitter_stddev_ms: f64,
+    pub jitter_p99_ms: f64,
+    pub late: u64,
+    pub events: u64,
+}
+
+impl ClickStats {
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    pub fn set_limit(&mut self, limit: Option<RunLimit>) {
+        self.limit = limit;
+    }
+
+    pub fn limit(&self) -> Option<&RunLimit> {
+        self.limit.as_ref()
+    }
+
+    /// Press/release pairs finished so far.
+    pub fn completed(&self) -> u64 {
+        self.clicks - self.pending_press.is_some() as u64
+    }
+
+    pub fn record_press(&mut self, deadline: Instant, at: Instant) {
+        self.clicks += 1;
+        self.first_press.get_or_insert(at);
+        self.last_press = Some(at);
+        self.pending_press = Some(at);
+        self.record_jitter(deadline, at);
+    }
+
+    pub fn record_release(&mut self, deadline: Instant, at: Instant) {
+        if let Some(p) = self.pending_press.take() {
+            self.held_secs += at.saturating_duration_since(p).as_secs_f64();
+        }
+        self.record_jitter(deadline, at);
+    }
+
+    /// A wheel turn in scroll mode: a click without a hold.
+    pub fn record_scroll(&mut self, deadline: Instant, at: Instant) {
+        self.clicks += 1;
+        self.scrolls += 1;
+        self.first_press.get_or_insert(at);
+        self.last_press = Some(at);
+        self.record_jitter(deadline, at);
+    }
+
+    fn record_jitter(&mut self, deadline: Instant, at: Instant) {
+        // Signed lateness: negative if we fired early
+        let ms = if at >= deadline {
+            at.duration_since(deadline).as_secs_f64() * 1000.0
+        } else {
+            -(deadline.duration_since(at).as_secs_f64() * 1000.0)
+        };
+        if ms > LATE_THRESHOLD_MS {
+            self.late += 1;
+        }
+
+        self.events += 1;
+        let d = ms - self.mean;
+        self.mean += d / self.events as f64;
+        self.m2 += d * (ms - self.mean);
+
+        if self.recent.len() == RECENT_SAMPLES {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(ms);
+    }
+
+    pub fn summary(&self) -> StatsSummary {
+        // Rate over the span between first and last press
+        let span = match (self.first_press, self.last_press) {
+            (Some(a), Some(b)) if self.clicks > 1 => b.duration_since(a).as_secs_f64(),
+            _ => 0.0,
+        };
+        let cps = (span > 0.0).then(|| (self.clicks - 1) as f64 / span);
+
+        // Mean hold over mean period; completed clicks only, none when
+        // scrolling
+        let released = self.completed();
+        let duty = cps
+            .filter(|_| released > 0 && self.scrolls == 0)
+            .map(|cps| self.held_secs / released as f64 * cps * 100.0);
+
+        let stddev = if self.events > 1 {
+            (self.m2 / (self.events - 1) as f64).sqrt()
+        } else {
+            0.0
+        };
+
+        let p99 = if self.recent.is_empty() {
+            0.0
+        } else {
+            let mut v: Vec<f64> = self.recent.iter().copied().collect();
+            v.sort_by(|a, b| a.total_cmp(b));
+            let idx = ((v.len() as f64 * 0.99).ceil() as usize).clamp(1, v.len()) - 1;
+            v[idx]
+        };
+
+        StatsSummary {
+            clicks: self.clicks,
+            cps,
+            duty,
+            jitter_mean_ms: self.mean,
+            jitter_stddev_ms: stddev,
+            jitter_p99_ms: p99,
+            late: self.late,
+            events: self.events,
+        }
+    }
+}
@@ -0,0 +1,119 @@
+// ---------- Human takeover ----------
+// While clicking, takeover_thread watches raw pointer input from the real
+// devices (not XTEST or our uinput device) and stops the run as soon as
+// the user moves the mouse further than `distance` or presses a button.
+
+use std::{
+    os::raw::c_int,
+    ptr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use x11::{
+    xinput2::{XI_RawButtonPress, XI_RawMotion},
+    xlib::*,
+};
+
+use crate::{events::Reporter, xi, Settings};
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Takeover {
+    pub enabled: bool,
+    /// Pixels the pointer may drift before it counts as the user's hand.
+    pub distance: f64,
+    pub on_button: bool,
+}
+
+impl Default for Takeover {
+    fn default() -> Self {
+        Self { enabled: false, distance: 50.0, on_button: true }
+    }
+}
+
+/// Movement further apart than this starts a new measurement, so a slow
+/// drift (a bumped desk) never adds up to a takeover.
+const SETTLE: Duration = Duration::from_secs(1);
+
+pub fn takeover_thread(
+    running: Arc<AtomicBool>,
+    should_exit: Arc<AtomicBool>,
+    settings: Arc<Mutex<Settings>>,
+    report: Reporter,
+) -> Result<()> {
+    unsafe {
+        XInitThreads();
+        let dpy = XOpenDisplay(ptr::null());
+        if dpy.is_null() {
+            bail!("Takeover watch: failed to open X display (X11 only)");
+        }
+        let root = XDefaultRootWindow(dpy);
+        let opcode = xi::opcode(dpy);
+        let mut sources = xi::Sources::default();
+        let mut watching = false;
+        let mut warned = false;
+        // Net movement since the last pause of SETTLE
+        let mut moved = (0.0, 0.0);
+        let mut last_motion = Instant::now();
+
+        while !should_exit.load(Ordering::SeqCst) {
+            let t = settings.lock().unwrap().takeover.clone();
+            let want = t.enabled && running.load(Ordering::SeqCst);
+            if want && opcode.is_none() && !warned {
+                report.status("XInput 2.2 is not available, the takeover fail-safe is off");
+                warned = true;
+            }
+            if let Some(op) = opcode {
+                if want != watching {
+                    let events: &[c_int] = if want { &[XI_RawMotion, XI_RawButtonPress] } else { &[] };
+                    xi::select_raw(dpy, root, events);
+                    XSync(dpy, False);
+                    watching = want;
+                    moved = (0.0, 0.0);
+                }
+
+                let mut why = None;
+                while XPending(dpy) > 0 {
+                    let mut event: XEvent = std::mem::zeroed();
+                    XNextEvent(dpy, &mut event);
+                    let Some(r) = xi::raw_event(dpy, &mut event, op) else { continue };
+                    // Events already queued when we stopped watching
+                    if !watching || sources.is_injected(dpy, r.sourceid) {
+                        continue;
+                    }
+                    if r.evtype == XI_RawButtonPress && t.on_button {
+                        why = Some(format!("mouse button {} pressed", r.detail));
+                    } else if r.evtype == XI_RawMotion {
+                        if last_motion.elapsed() > SETTLE {
+                            moved = (0.0, 0.0);
+                        }
+                        last_motion = Instant::now();
+                        moved.0 += r.delta.0;
+                        moved.1 += r.delta.1;
+                        if f64::hypot(moved.0, moved.1) > t.distance {
+                            why = Some("mouse moved".to_string());
+                        }
+                    }
+                }
+                if let Some(why) = why {
+                    if running.swap(false, Ordering::SeqCst) {
+                        report.status(format!("stopped: {why}"));
+                    }
+                    moved = (0.0, 0.0);
+                }
+            }
+
+            // Settings and `running` change without X events; look again soon
+            let mut fd = libc::pollfd { fd: XConnectionNumber(dpy), events: libc::POLLIN, revents: 0 };
+            libc::poll(&mut fd, 1, 100);
+        }
+        XCloseDisplay(dpy);
+    }
+    Ok(())
+}
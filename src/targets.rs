@@ -0,0 +1,103 @@
+// ---------- Click targets ----------
+// With targets enabled, click_thread moves the pointer to a stored screen
+// point before each press, stepping through the list in order or at
+// random, and optionally puts the pointer back after the release.
+
+use serde::{Deserialize, Serialize};
+use x11::xlib::*;
+use x11::xtest::XTestFakeMotionEvent;
+
+use crate::humanize::Humanizer;
+
+#[derive(Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct Point {
+    pub x: i32,
+    pub y: i32,
+}
+
+#[derive(Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TargetOrder {
+    #[default]
+    Sequential,
+    Random,
+}
+
+#[derive(Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Targets {
+    pub enabled: bool,
+    pub points: Vec<Point>,
+    pub order: TargetOrder,
+    /// Move the pointer back to where the user had it after each click.
+    pub restore: bool,
+}
+
+impl Targets {
+    pub fn active(&self) -> bool {
+        self.enabled && !self.points.is_empty()
+    }
+}
+
+/// A window that receives synthetic clicks (BackendKind::Window) instead
+/// of the pointer moving there.
+#[derive(Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TargetWindow {
+    /// Client window id; only meaningful for the X session it was picked in.
+    pub id: u64,
+    /// WM_CLASS class and _NET_WM_NAME when picked, for display.
+    pub class: String,
+    pub title: String,
+    /// Click point relative to the window.
+    pub at: Point,
+}
+
+/// Picks the point for each click of a run.
+#[derive(Default)]
+pub struct TargetCycler {
+    next: usize,
+}
+
+impl TargetCycler {
+    pub fn pick(&mut self, t: &Targets, rng: &mut Humanizer) -> Option<Point> {
+        if !t.active() {
+            return None;
+        }
+        let n = t.points.len();
+        let i = match t.order {
+            TargetOrder::Sequential => {
+                let i = self.next % n;
+                self.next = (i + 1) % n;
+                i
+            }
+            TargetOrder::Random => rng.index(n),
+        };
+        Some(t.points[i])
+    }
+}
+
+/// Current pointer position in root coordinates.
+pub unsafe fn pointer_position(dpy: *mut Display) -> Option<Point> {
+    let root = XDefaultRootWindow(dpy);
+    let (mut root_ret, mut child) = (0, 0);
+    let (mut x, mut y, mut wx, mut wy) = (0, 0, 0, 0);
+    let mut mask = 0;
+    let same_screen = XQueryPointer(
+        dpy,
+        root,
+        &mut root_ret,
+        &mut child,
+        &mut x,
+        &mut y,
+        &mut wx,
+        &mut wy,
+        &mut mask,
+    );
+    (same_screen != 0).then_some(Point { x, y })
+}
+
+/// Move the pointer via XTest so it looks like real motion to clients.
+pub unsafe fn move_pointer(dpy: *mut Display, p: Point) {
+    XTestFakeMotionEvent(dpy, -1, p.x, p.y, CurrentTime);
+}
@@ -0,0 +1,51 @@
+// ---------- Wakeup ----------
+// hotkey_thread sleeps in poll() on the X connection; an eventfd next to it
+// lets other threads wake it when the hotkey settings change or when it
+// should exit.
+
+use std::{io, os::fd::RawFd};
+
+use anyhow::Result;
+
+pub struct Wakeup {
+    fd: RawFd,
+}
+
+impl Wakeup {
+    pub fn new() -> Result<Self> {
+        let fd = unsafe { libc::eventfd(0, libc::EFD_CLOEXEC | libc::EFD_NONBLOCK) };
+        if fd < 0 {
+            return Err(io::Error::last_os_error().into());
+        }
+        Ok(Self { fd })
+    }
+
+    pub fn notify(&self) {
+        let one: u64 = 1;
+        // Only fails if the counter would overflow, i.e. already signalled
+        unsafe { libc::write(self.fd, &one as *const u64 as *const libc::c_void, 8) };
+    }
+
+    /// Reset after waking so the next poll() blocks again.
+    pub fn drain(&self) {
+        let mut n: u64 = 0;
+        unsafe { libc::read(self.fd, &mut n as *mut u64 as *mut libc::c_void, 8) };
+    }
+
+    /// Block until `other` is readable or notify() was called.
+    pub fn wait_with(&self, other: RawFd) {
+        let mut fds = [
+            libc::pollfd { fd: other, events: libc::POLLIN, revents: 0 },
+            libc::pollfd { fd: self.fd, events: libc::POLLIN, revents: 0 },
+        ];
+        // EINTR just means we look around once more
+        unsafe { libc::poll(fds.as_mut_ptr(), fds.len() as libc::nfds_t, -1) };
+        self.drain();
+    }
+}
+
+impl Drop for Wakeup {
+    fn drop(&mut self) {
+        unsafe { libc::close(self.fd) };
+    }
+}
@@ -0,0 +1,85 @@
+// ---------- X error trapping ----------
+// Xlib reports protocol errors (e.g. BadAccess from XGrabKey when another
+// client owns the key) asynchronously through one process-wide handler.
+// `trap` installs a handler that collects errors for displays currently
+// inside it and passes everything else on to the previous handler. winit
+// sets its own handler (without chaining) when the GUI connection opens,
+// possibly after our first trap, so every trap puts ours back on top.
+
+use std::{
+    os::raw::{c_int, c_ulong},
+    sync::Mutex,
+};
+
+use x11::xlib::*;
+
+type Handler = unsafe extern "C" fn(*mut Display, *mut XErrorEvent) -> c_int;
+
+#[derive(Clone, Copy, Debug)]
+pub struct XError {
+    pub serial: c_ulong,
+    pub error_code: u8,
+}
+
+impl XError {
+    pub fn is_bad_access(&self) -> bool {
+        self.error_code == BadAccess
+    }
+}
+
+struct Trap {
+    display: usize,
+    errors: Vec<XError>,
+}
+
+static PREVIOUS: Mutex<Option<Handler>> = Mutex::new(None);
+static TRAPS: Mutex<Vec<Trap>> = Mutex::new(Vec::new());
+
+unsafe extern "C" fn on_error(dpy: *mut Display, ev: *mut XErrorEvent) -> c_int {
+    let ev = &*ev;
+    {
+        let mut traps = TRAPS.lock().unwrap();
+        if let Some(t) = traps.iter_mut().find(|t| t.display == dpy as usize) {
+            t.errors.push(XError {
+                serial: ev.serial,
+                error_code: ev.error_code,
+            });
+            return 0;
+        }
+    }
+    let previous = *PREVIOUS.lock().unwrap();
+    match previous {
+        Some(h) => h(dpy, ev as *const _ as *mut _),
+        None => {
+            eprintln!(
+                "[x11] error {} on request {} (serial {})",
+                ev.error_code, ev.request_code, ev.serial
+            );
+            0
+        }
+    }
+}
+
+/// Make on_error the active handler, remembering whoever replaced it.
+unsafe fn install() {
+    let mut previous = PREVIOUS.lock().unwrap();
+    let prev = XSetErrorHandler(Some(on_error));
+    if !prev.is_some_and(|h| std::ptr::fn_addr_eq(h, on_error as Handler)) {
+        *previous = prev;
+    }
+}
+
+/// Run `f` against `dpy`, wait for the server to process it and return any
+/// protocol errors it caused. Other displays' errors are unaffected.
+pub unsafe fn trap(dpy: *mut Display, f: impl FnOnce()) -> Vec<XError> {
+    install();
+    TRAPS.lock().unwrap().push(Trap {
+        display: dpy as usize,
+        errors: Vec::new(),
+    });
+    f();
+    XSync(dpy, False);
+    let mut traps = TRAPS.lock().unwrap();
+    let i = traps.iter().position(|t| t.display == dpy as usize).unwrap();
+    traps.remove(i).errors
+}
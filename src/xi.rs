@@ -0,0 +1,135 @@
+// ---------- XInput2 raw events ----------
+// Raw device events reach every client that selected them on the root
+// window, even while another client (or we ourselves) holds a grab. The
+// hotkey thread uses them to see mouse-button hotkeys being released after
+// it has let go of the pointer grab the press started, as the passive
+// "raw" hotkey backend that grabs nothing at all, and to notice the user
+// taking over the mouse.
+
+use std::{ffi::CStr, os::raw::c_int, slice};
+
+use x11::{xinput2::*, xlib::*};
+
+/// A decoded XI_Raw* event.
+#[derive(Clone, Copy, Debug)]
+pub struct RawEvent {
+    pub evtype: c_int,
+    /// Key code or button number.
+    pub detail: c_int,
+    /// The physical (slave) device it came from.
+    pub sourceid: c_int,
+    /// Pointer motion along x and y (valuators 0 and 1), accelerated.
+    pub delta: (f64, f64),
+}
+
+/// The XInputExtension major opcode, if the server speaks XI 2.2 or later
+/// (raw events during grabs need 2.1+).
+pub unsafe fn opcode(dpy: *mut Display) -> Option<c_int> {
+    let name = c"XInputExtension";
+    let (mut opcode, mut event, mut error) = (0, 0, 0);
+    if XQueryExtension(dpy, name.as_ptr(), &mut opcode, &mut event, &mut error) == 0 {
+        return None;
+    }
+    let (mut major, mut minor) = (2, 2);
+    if XIQueryVersion(dpy, &mut major, &mut minor) != Success as c_int {
+        return None;
+    }
+    Some(opcode)
+}
+
+/// Ids of the devices our own input arrives from: XTEST ("Virtual core
+/// XTEST pointer/keyboard") and the uinput click backend's device.
+unsafe fn injecting_devices(dpy: *mut Display) -> Vec<c_int> {
+    let mut n = 0;
+    let info = XIQueryDevice(dpy, XIAllDevices, &mut n);
+    if info.is_null() {
+        return Vec::new();
+    }
+    let ids = slice::from_raw_parts(info, n as usize)
+        .iter()
+        .filter(|d| {
+            let name = CStr::from_ptr(d.name).to_bytes();
+            name.windows(5).any(|w| w == b"XTEST") || name == crate::backend::DEVICE_NAME.as_bytes()
+        })
+        .map(|d| d.deviceid)
+        .collect();
+    XIFreeDeviceInfo(info);
+    ids
+}
+
+/// Tells our own input devices from the user's. Devices are listed again
+/// the first time an unknown id shows up, since the uinput device only
+/// appears once that backend is opened.
+#[derive(Default)]
+pub struct Sources {
+    injected: Vec<c_int>,
+    real: Vec<c_int>,
+}
+
+impl Sources {
+    pub unsafe fn is_injected(&mut self, dpy: *mut Display, id: c_int) -> bool {
+        if self.real.contains(&id) {
+            return false;
+        }
+        if !self.injected.contains(&id) {
+            self.injected = injecting_devices(dpy);
+            if !self.injected.contains(&id) {
+                self.real.push(id);
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Ask for the given XI_Raw* event types from all master devices,
+/// replacing the previous selection.
+pub unsafe fn select_raw(dpy: *mut Display, root: Window, events: &[c_int]) {
+    let mut bits = [0u8; (XI_LASTEVENT as usize >> 3) + 1];
+    for &ev in events {
+        bits[ev as usize >> 3] |= 1 << (ev & 7);
+    }
+    let mut mask = XIEventMask {
+        deviceid: XIAllMasterDevices,
+        mask_len: bits.len() as c_int,
+        mask: bits.as_mut_ptr(),
+    };
+    XISelectEvents(dpy, root, &mut mask, 1);
+}
+
+/// Decode `event` if it is a raw event from the extension with `opcode`.
+pub unsafe fn raw_event(dpy: *mut Display, event: &mut XEvent, opcode: c_int) -> Option<RawEvent> {
+    let cookie = &mut event.generic_event_cookie;
+    if cookie.type_ != GenericEvent || cookie.extension != opcode {
+        return None;
+    }
+    if XGetEventData(dpy, cookie) == 0 {
+        return None;
+    }
+    let raw = &*(cookie.data as *const XIRawEvent);
+    let out = RawEvent {
+        evtype: cookie.evtype,
+        detail: raw.detail,
+        sourceid: raw.sourceid,
+        delta: motion(&raw.valuators),
+    };
+    XFreeEventData(dpy, cookie);
+    Some(out)
+}
+
+/// Valuators 0 and 1 of `v`; values are packed for the bits set in the mask.
+unsafe fn motion(v: &XIValuatorState) -> (f64, f64) {
+    if v.mask.is_null() || v.values.is_null() {
+        return (0.0, 0.0);
+    }
+    let mask = slice::from_raw_parts(v.mask, v.mask_len as usize);
+    let set = |i: usize| mask.get(i >> 3).is_some_and(|b| b & (1 << (i & 7)) != 0);
+    let (mut out, mut n) = ([0.0; 2], 0);
+    for (i, o) in out.iter_mut().enumerate() {
+        if set(i) {
+            *o = *v.values.add(n);
+            n += 1;
+        }
+    }
+    (out[0], out[1])
+}